@@ -17,7 +17,7 @@ mod download {
     use flate2::read::GzDecoder;
     use std::fs::File;
     use std::io::{self, BufRead, BufReader, Cursor, Read};
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use std::str::FromStr;
     use tar::Archive;
 
@@ -27,33 +27,46 @@ mod download {
         target_os = "macos",
         any(target_arch = "x86_64", target_arch = "aarch64"),
     ))]
-    fn download_filename() -> String {
-        if cfg!(not(feature = "23_1")) {
-            format!("bitcoin-{}-osx64.tar.gz", &VERSION)
+    fn download_filename(version: &str) -> String {
+        if major(version) < 23 {
+            format!("bitcoin-{}-osx64.tar.gz", version)
         } else {
-            format!("bitcoin-{}-x86_64-apple-darwin.tar.gz", &VERSION)
+            format!("bitcoin-{}-x86_64-apple-darwin.tar.gz", version)
         }
     }
 
     #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-    fn download_filename() -> String {
-        format!("bitcoin-{}-x86_64-linux-gnu.tar.gz", &VERSION)
+    fn download_filename(version: &str) -> String {
+        format!("bitcoin-{}-x86_64-linux-gnu.tar.gz", version)
     }
 
     #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-    fn download_filename() -> String {
-        format!("bitcoin-{}-aarch64-linux-gnu.tar.gz", &VERSION)
+    fn download_filename(version: &str) -> String {
+        format!("bitcoin-{}-aarch64-linux-gnu.tar.gz", version)
     }
 
     #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
-    fn download_filename() -> String {
-        format!("bitcoin-{}-win64.zip", &VERSION)
+    fn download_filename(version: &str) -> String {
+        format!("bitcoin-{}-win64.zip", version)
     }
 
-    fn get_expected_sha256(filename: &str) -> anyhow::Result<sha256::Hash> {
-        let sha256sums_filename = format!("sha256/bitcoin-core-{}-SHA256SUMS", &VERSION);
-        #[cfg(not(feature = "22_1"))]
-        let sha256sums_filename = format!("{}.asc", sha256sums_filename);
+    /// Major version number, eg. 21 for "0.21.2" and 22 for "22.1"
+    fn major(version: &str) -> u32 {
+        let mut components = version.split('.');
+        let first = components.next().unwrap_or("0");
+        let major = if first == "0" {
+            components.next().unwrap_or("0")
+        } else {
+            first
+        };
+        major.parse().unwrap()
+    }
+
+    fn get_expected_sha256(version: &str, filename: &str) -> anyhow::Result<sha256::Hash> {
+        let mut sha256sums_filename = format!("sha256/bitcoin-core-{}-SHA256SUMS", version);
+        if major(version) < 22 {
+            sha256sums_filename.push_str(".asc");
+        }
         let file = File::open(&sha256sums_filename)
             .with_context(|| format!("cannot find {:?}", sha256sums_filename))?;
         for line in BufReader::new(file).lines().flatten() {
@@ -74,8 +87,31 @@ mod download {
         if std::env::var_os("BITCOIND_SKIP_DOWNLOAD").is_some() {
             return Ok(());
         }
-        let download_filename = download_filename();
-        let expected_hash = get_expected_sha256(&download_filename)?;
+        if cfg!(feature = "all_versions") {
+            // downloading every version would overload the download endpoint in CI
+            let tarball_dir = std::env::var("BITCOIND_TARBALL_DIR").ok();
+            for version in SUPPORTED_VERSIONS {
+                let tarball_file = tarball_dir
+                    .as_ref()
+                    .map(|dir| Path::new(dir).join(download_filename(version)));
+                install(version, tarball_file, true)?;
+            }
+            Ok(())
+        } else {
+            let tarball_file = std::env::var("BITCOIND_TARBALL_FILE").ok().map(Into::into);
+            install(VERSION, tarball_file, false)
+        }
+    }
+
+    /// Install the bitcoind executable of the given `version` in `OUT_DIR`, from `tarball_file` if
+    /// given or else downloading it unless `tarball_required`
+    fn install(
+        version: &str,
+        tarball_file: Option<PathBuf>,
+        tarball_required: bool,
+    ) -> anyhow::Result<()> {
+        let download_filename = download_filename(version);
+        let expected_hash = get_expected_sha256(version, &download_filename)?;
         let out_dir = std::env::var_os("OUT_DIR").unwrap();
 
         let mut bitcoin_exe_home = Path::new(&out_dir).join("bitcoin");
@@ -84,24 +120,30 @@ mod download {
                 .with_context(|| format!("cannot create dir {:?}", bitcoin_exe_home))?;
         }
         let existing_filename = bitcoin_exe_home
-            .join(format!("bitcoin-{}", VERSION))
+            .join(format!("bitcoin-{}", version))
             .join("bin")
             .join("bitcoind");
 
         if !existing_filename.exists() {
             println!(
                 "filename:{} version:{} hash:{}",
-                download_filename, VERSION, expected_hash
+                download_filename, version, expected_hash
             );
 
-            let (file_or_url, tarball_bytes) = match std::env::var("BITCOIND_TARBALL_FILE") {
-                Err(_) => {
+            let (file_or_url, tarball_bytes) = match tarball_file {
+                None if tarball_required => {
+                    return Err(anyhow::anyhow!(
+                        "bitcoind {} not found, the all_versions feature requires the tarballs in the BITCOIND_TARBALL_DIR env var",
+                        version
+                    ));
+                }
+                None => {
                     let download_endpoint = std::env::var("BITCOIND_DOWNLOAD_ENDPOINT")
                         .unwrap_or("https://bitcoincore.org/bin".to_owned());
 
                     let url = format!(
                         "{}/bitcoin-core-{}/{}",
-                        download_endpoint, VERSION, download_filename
+                        download_endpoint, version, download_filename
                     );
                     let resp = minreq::get(&url)
                         .send()
@@ -110,17 +152,17 @@ mod download {
 
                     (url, resp.as_bytes().to_vec())
                 }
-                Ok(path) => {
+                Some(path) => {
                     let f = File::open(&path).with_context(|| {
                         format!(
-                            "Cannot find {:?} specified with env var BITCOIND_TARBALL_FILE",
+                            "Cannot find {:?} specified with env var BITCOIND_TARBALL_FILE or BITCOIND_TARBALL_DIR",
                             &path
                         )
                     })?;
                     let mut reader = BufReader::new(f);
                     let mut buffer = Vec::new();
                     reader.read_to_end(&mut buffer)?;
-                    (path, buffer)
+                    (path.display().to_string(), buffer)
                 }
             };
 
@@ -0,0 +1,161 @@
+//! Async flavour of [crate::BitcoinD] to be used from tokio tests.
+//!
+//! Requires the `tokio` feature. [BitcoinDAsync] wraps a [BitcoinD] launched with
+//! [BitcoinD::with_conf_async]: the node process is handled with [tokio::process] and waiting
+//! happens with [tokio::time::sleep] so that the runtime is never blocked while the node starts or
+//! stops. RPC calls are still made with the blocking [crate::bitcoincore_rpc::Client], the
+//! [BitcoinD] fields and methods are reachable through [Deref].
+
+use crate::bitcoincore_rpc::RpcApi;
+use crate::{BitcoinD, Conf, Result};
+use std::ffi::OsStr;
+use std::ops::Deref;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+
+/// A [BitcoinD] spawned with [tokio::process::Command], with async versions of the methods
+/// waiting for the node
+#[derive(Debug)]
+pub struct BitcoinDAsync {
+    bitcoind: BitcoinD,
+}
+
+impl Deref for BitcoinDAsync {
+    type Target = BitcoinD;
+
+    fn deref(&self) -> &BitcoinD {
+        &self.bitcoind
+    }
+}
+
+impl BitcoinDAsync {
+    /// Launch the bitcoind process from the given `exe` executable with default args.
+    pub async fn new<S: AsRef<OsStr>>(exe: S) -> Result<BitcoinDAsync> {
+        BitcoinDAsync::with_conf(exe, &Conf::default()).await
+    }
+
+    /// Launch the bitcoind process from the given `exe` executable with given [Conf] param, see
+    /// [BitcoinD::with_conf_async]
+    pub async fn with_conf<S: AsRef<OsStr>>(exe: S, conf: &Conf<'_>) -> Result<BitcoinDAsync> {
+        Ok(BitcoinDAsync {
+            bitcoind: BitcoinD::with_conf_async(exe, conf).await?,
+        })
+    }
+
+    /// Stop the node, waiting correct process termination without blocking the runtime, see
+    /// [BitcoinD::stop_async]
+    #[must_use = "the stop result should be checked to ensure a clean shutdown"]
+    pub async fn stop(&mut self) -> Result<ExitStatus> {
+        self.bitcoind.stop_async().await
+    }
+
+    /// Wait until the node chain reaches `height`, polling every 100ms.
+    ///
+    /// Returns an error if the height isn't reached within `timeout`
+    #[must_use = "the wait result should be checked, an error means the condition wasn't met in time"]
+    pub async fn wait_for_block(&self, height: u64, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let current = self.client.get_block_count()?;
+            if current >= height {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!(
+                    "height {} not reached within {:?}, current height {}",
+                    height,
+                    timeout,
+                    current
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Wait until the node mempool contains at least `size` transactions, polling every 100ms.
+    ///
+    /// Returns an error if the size isn't reached within `timeout`
+    #[must_use = "the wait result should be checked, an error means the condition wasn't met in time"]
+    pub async fn wait_for_mempool_size(&self, size: usize, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let current = self.client.get_raw_mempool()?.len();
+            if current >= size {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!(
+                    "mempool size {} not reached within {:?}, current size {}",
+                    size,
+                    timeout,
+                    current
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::async_bitcoind::BitcoinDAsync;
+    use crate::bitcoincore_rpc::RpcApi;
+    use crate::{exe_path, Conf};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_async_initial_blocks() {
+        let conf = Conf {
+            initial_blocks: 101,
+            ..Conf::default()
+        };
+        let mut bitcoind = BitcoinDAsync::with_conf(exe_path().unwrap(), &conf)
+            .await
+            .unwrap();
+        assert_eq!(101, bitcoind.client.get_block_count().unwrap());
+        assert!(bitcoind.stop().await.unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn test_async() {
+        let mut bitcoind = BitcoinDAsync::new(exe_path().unwrap()).await.unwrap();
+        assert_eq!(0, bitcoind.client.get_blockchain_info().unwrap().blocks);
+
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        bitcoind
+            .wait_for_block(101, Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(bitcoind
+            .wait_for_block(102, Duration::from_millis(300))
+            .await
+            .is_err());
+
+        bitcoind
+            .client
+            .send_to_address(
+                &address,
+                crate::bitcoincore_rpc::bitcoin::Amount::from_sat(10_000),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        bitcoind
+            .wait_for_mempool_size(1, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        assert!(bitcoind.stop().await.unwrap().success());
+    }
+}
@@ -0,0 +1,94 @@
+//! Deprecated versions of the functions returning [anyhow::Result] before [crate::Result] was
+//! introduced, kept for one release to migrate code that names `anyhow::Result` or downcasts
+//! the errors.
+//!
+//! Methods are exposed as functions taking the [BitcoinD] as first parameter, eg.
+//! `compat::stop(&mut bitcoind)` for `bitcoind.stop()`.
+#![allow(deprecated)]
+
+#[cfg(any(feature = "0_19_1", not(feature = "download")))]
+use crate::bitcoincore_rpc::Client;
+use crate::{BitcoinD, Conf};
+use std::ffi::OsStr;
+use std::process::ExitStatus;
+
+/// Launch the bitcoind process from the given `exe` executable with default args
+#[deprecated(note = "use BitcoinD::new returning bitcoind::Result")]
+pub fn new<S: AsRef<OsStr>>(exe: S) -> anyhow::Result<BitcoinD> {
+    Ok(BitcoinD::new(exe)?)
+}
+
+/// Launch the bitcoind process from the given `exe` executable with given [Conf] param
+#[deprecated(note = "use BitcoinD::with_conf returning bitcoind::Result")]
+pub fn with_conf<S: AsRef<OsStr>>(exe: S, conf: &Conf) -> anyhow::Result<BitcoinD> {
+    Ok(BitcoinD::with_conf(exe, conf)?)
+}
+
+/// Stop the node, waiting correct process termination
+#[deprecated(note = "use BitcoinD::stop returning bitcoind::Result")]
+pub fn stop(bitcoind: &mut BitcoinD) -> anyhow::Result<ExitStatus> {
+    Ok(bitcoind.stop()?)
+}
+
+#[cfg(any(feature = "0_19_1", not(feature = "download")))]
+/// Create a new wallet in the running node, and return an RPC client connected to the just
+/// created wallet
+#[deprecated(note = "use BitcoinD::create_wallet returning bitcoind::Result")]
+pub fn create_wallet<T: AsRef<str>>(bitcoind: &BitcoinD, wallet: T) -> anyhow::Result<Client> {
+    Ok(bitcoind.create_wallet(wallet)?)
+}
+
+#[cfg(feature = "download")]
+/// create BitcoinD struct with the downloaded executable.
+#[deprecated(note = "use BitcoinD::from_downloaded returning bitcoind::Result")]
+pub fn from_downloaded() -> anyhow::Result<BitcoinD> {
+    Ok(BitcoinD::from_downloaded()?)
+}
+
+#[cfg(feature = "download")]
+/// create BitcoinD struct with the downloaded executable and given Conf.
+#[deprecated(note = "use BitcoinD::from_downloaded_with_conf returning bitcoind::Result")]
+pub fn from_downloaded_with_conf(conf: &Conf) -> anyhow::Result<BitcoinD> {
+    Ok(BitcoinD::from_downloaded_with_conf(conf)?)
+}
+
+/// Returns a non-used local port if available.
+#[deprecated(note = "use bitcoind::get_available_port returning bitcoind::Result")]
+pub fn get_available_port() -> anyhow::Result<u16> {
+    Ok(crate::get_available_port()?)
+}
+
+/// Provide the bitcoind executable path if a version feature has been specified
+#[deprecated(note = "use bitcoind::downloaded_exe_path returning bitcoind::Result")]
+pub fn downloaded_exe_path() -> anyhow::Result<String> {
+    Ok(crate::downloaded_exe_path()?)
+}
+
+/// Returns the daemon `bitcoind` executable with the following precedence, see
+/// [crate::exe_path]
+#[deprecated(note = "use bitcoind::exe_path returning bitcoind::Result")]
+pub fn exe_path() -> anyhow::Result<String> {
+    Ok(crate::exe_path()?)
+}
+
+/// Validate the specified arg if there is any unavailable or deprecated one
+#[deprecated(note = "use bitcoind::validate_args returning bitcoind::Result")]
+pub fn validate_args(args: Vec<&str>) -> anyhow::Result<Vec<&str>> {
+    Ok(crate::validate_args(args)?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::compat;
+    use crate::exe_path;
+
+    #[test]
+    fn test_compat() {
+        let err = compat::validate_args(vec!["-rpcuser=bitcoind"]).unwrap_err();
+        assert!(err.downcast_ref::<crate::Error>().is_some());
+
+        assert_eq!(compat::exe_path().unwrap(), exe_path().unwrap());
+        let mut bitcoind = compat::new(exe_path().unwrap()).unwrap();
+        compat::stop(&mut bitcoind).unwrap();
+    }
+}
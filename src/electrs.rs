@@ -0,0 +1,189 @@
+//! Launch an `electrs` Electrum server indexing a running [BitcoinD], enabled with the `electrs`
+//! feature.
+//!
+//! Supported is the [esplora](https://github.com/Blockstream/electrs) flavour of electrs, which
+//! syncs through the bitcoind RPC with `--jsonrpc-import` so the p2p port isn't needed. The
+//! [romanz](https://github.com/romanz/electrs) flavour takes different args and isn't supported.
+
+use crate::bitcoincore_rpc::bitcoin::Network;
+use crate::bitcoincore_rpc::RpcApi;
+use crate::{get_available_port, BitcoinD, DataDir, Error, Result, LOCAL_IP};
+use anyhow::Context;
+use log::{debug, error};
+use std::ffi::OsStr;
+use std::net::{SocketAddrV4, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Struct representing the electrs process with related information
+pub struct ElectrsD {
+    /// Process child handle, killed when dropped
+    process: Child,
+    /// Work directory, where electrs stores its database
+    work_dir: DataDir,
+
+    /// Contains information to connect to this electrs server
+    pub params: ConnectParams,
+}
+
+#[derive(Debug, Clone)]
+/// Contains all the information to connect to this electrs server
+pub struct ConnectParams {
+    /// Electrum protocol socket, eg. connect with `tcp://{electrum_socket}`
+    pub electrum_socket: SocketAddrV4,
+    /// Prometheus monitoring socket
+    pub monitoring_socket: SocketAddrV4,
+    /// Esplora HTTP REST API socket
+    pub http_socket: SocketAddrV4,
+}
+
+impl ElectrsD {
+    /// Launch electrs, found with [exe_path], indexing the given `bitcoind` node.
+    ///
+    /// Waits for the electrum port to accept connections before returning
+    pub fn new(bitcoind: &BitcoinD) -> Result<ElectrsD> {
+        ElectrsD::with_exe(exe_path()?, bitcoind)
+    }
+
+    /// Launch the electrs process from the given `exe` executable indexing the given `bitcoind`
+    /// node
+    pub fn with_exe<S: AsRef<OsStr>>(exe: S, bitcoind: &BitcoinD) -> Result<ElectrsD> {
+        let work_dir = DataDir::Temporary(TempDir::new()?);
+        let network = match bitcoind.client.get_blockchain_info()?.chain {
+            Network::Bitcoin => "mainnet".to_string(),
+            other => other.to_string(),
+        };
+        let electrum_socket = SocketAddrV4::new(LOCAL_IP, get_available_port()?);
+        let monitoring_socket = SocketAddrV4::new(LOCAL_IP, get_available_port()?);
+        let http_socket = SocketAddrV4::new(LOCAL_IP, get_available_port()?);
+        let cookie = bitcoind
+            .params
+            .get_cookie_values()
+            .context("cannot read the bitcoind cookie")?;
+
+        let args = vec![
+            "-vvv".to_string(),
+            format!("--db-dir={}", work_dir.path().display()),
+            format!("--network={}", network),
+            format!("--daemon-dir={}", bitcoind.workdir().display()),
+            format!("--daemon-rpc-addr={}", bitcoind.params.rpc_socket),
+            "--jsonrpc-import".to_string(),
+            format!("--electrum-rpc-addr={}", electrum_socket),
+            format!("--monitoring-addr={}", monitoring_socket),
+            format!("--http-addr={}", http_socket),
+        ];
+
+        debug!("launching {:?} with args: {:?}", exe.as_ref(), args);
+
+        // passed after logging the args to keep the credentials out of the logs
+        let cookie_arg = format!("--cookie={}:{}", cookie.user, cookie.password);
+
+        let mut process = Command::new(exe.as_ref())
+            .args(&args)
+            .arg(cookie_arg)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                Error::ProcessSpawnFailed(exe.as_ref().to_string_lossy().to_string(), e)
+            })?;
+
+        // electrs opens the electrum port once the initial indexing is done
+        while TcpStream::connect(electrum_socket).is_err() {
+            if let Some(status) = process.try_wait()? {
+                error!("early exit with: {:?}", status);
+                return Err(Error::EarlyExit(status));
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        Ok(ElectrsD {
+            process,
+            work_dir,
+            params: ConnectParams {
+                electrum_socket,
+                monitoring_socket,
+                http_socket,
+            },
+        })
+    }
+
+    /// Returns the electrum URL including the schema eg. tcp://127.0.0.1:44842
+    pub fn electrum_url(&self) -> String {
+        format!("tcp://{}", self.params.electrum_socket)
+    }
+
+    /// Return the current workdir path of electrs
+    pub fn workdir(&self) -> PathBuf {
+        self.work_dir.path()
+    }
+
+    /// Kill the electrs process
+    pub fn kill(&mut self) -> Result<()> {
+        self.process.kill()?;
+        self.process.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for ElectrsD {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+    }
+}
+
+/// Returns the `electrs` executable with the following precedence:
+///
+/// 1) If it's specified in the `ELECTRS_EXE` env var
+/// 2) If there is no env var, the `electrs` executable is searched in the `PATH`
+pub fn exe_path() -> Result<String> {
+    if let Ok(path) = std::env::var("ELECTRS_EXE") {
+        return Ok(path);
+    }
+    which::which("electrs")
+        .map_err(|_| Error::NoElectrsExecutableFound)
+        .map(|p| p.display().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitcoincore_rpc::RpcApi;
+    use crate::electrs::ElectrsD;
+    use crate::{exe_path, BitcoinD};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_electrs() {
+        let bitcoind = BitcoinD::new(exe_path().unwrap()).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+
+        let electrsd = ElectrsD::new(&bitcoind).unwrap();
+        assert!(electrsd.electrum_url().starts_with("tcp://127.0.0.1:"));
+
+        let mut stream = TcpStream::connect(electrsd.params.electrum_socket).unwrap();
+        stream
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":0,\"method\":\"blockchain.headers.subscribe\",\"params\":[]}\n")
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert!(line.contains("\"height\":1"), "{}", line);
+
+        // the esplora REST API is served too
+        let mut stream = TcpStream::connect(electrsd.params.http_socket).unwrap();
+        stream
+            .write_all(b"GET /blocks/tip/height HTTP/1.0\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.ends_with("\r\n\r\n1"), "{}", response);
+    }
+}
@@ -1,17 +1,39 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![cfg_attr(feature = "doc", cfg_attr(all(), doc = include_str!("../README.md")))]
 
-mod versions;
+#[cfg(feature = "tokio")]
+pub mod async_bitcoind;
+pub mod compat;
+#[cfg(feature = "electrs")]
+pub mod electrs;
+pub mod rest;
+#[cfg(feature = "tarball")]
+mod tarball;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
+/// Versions of bitcoind which can be downloaded, `VERSION` is the one selected with a feature
+/// like `23_1` and it's available only with one of these features enabled
+pub mod versions;
+#[cfg(feature = "zmq")]
+pub mod zmq;
 
-use crate::bitcoincore_rpc::jsonrpc::serde_json::Value;
+use crate::bitcoincore_rpc::jsonrpc::serde_json::{self, json, Value};
 use anyhow::Context;
+use bitcoincore_rpc::bitcoin;
+use bitcoincore_rpc::bitcoin::hex::DisplayHex;
+use bitcoincore_rpc::bitcoin::Psbt;
+use bitcoincore_rpc::json::ScanningDetails;
 use bitcoincore_rpc::{Auth, Client, RpcApi};
 use log::{debug, error, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::io::{Read, Seek, SeekFrom};
 use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, ExitStatus, Stdio};
-use std::time::Duration;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, fmt, fs, thread};
 use tempfile::TempDir;
 
@@ -24,7 +46,7 @@ pub use which;
 /// Struct representing the bitcoind process with related information
 pub struct BitcoinD {
     /// Process child handle, used to terminate the process when this struct is dropped
-    process: Child,
+    process: Process,
     /// Rpc client linked to this bitcoind process
     pub client: Client,
     /// Work directory, where the node store blocks and other stuff.
@@ -32,6 +54,16 @@ pub struct BitcoinD {
 
     /// Contains information to connect to this node
     pub params: ConnectParams,
+
+    /// Network name as in [Conf::network], eg. `"regtest"`
+    pub network: String,
+
+    /// Whether [BitcoinD::stop] has been called, checked on drop in debug builds
+    stop_called: bool,
+    /// Whether the node started without the default wallet, see [Conf::no_wallet]
+    no_wallet: bool,
+    /// The `-fallbackfee` in [Conf::args], if any
+    fallback_fee: Option<bitcoin::FeeRate>,
 }
 
 #[derive(Debug)]
@@ -54,6 +86,12 @@ impl DataDir {
     }
 }
 
+impl fmt::Display for DataDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path().display())
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Contains all the information to connect to this node
 pub struct ConnectParams {
@@ -67,6 +105,9 @@ pub struct ConnectParams {
     pub zmq_pub_raw_block_socket: Option<SocketAddrV4>,
     /// zmq pub raw tx connection Url
     pub zmq_pub_raw_tx_socket: Option<SocketAddrV4>,
+    /// Network name as in [Conf::network], it's the name of the datadir subdirectory containing
+    /// the cookie file and the debug log
+    pub network: String,
 }
 
 pub struct CookieValues {
@@ -74,19 +115,109 @@ pub struct CookieValues {
     pub password: String,
 }
 
+impl CookieValues {
+    /// Convert the values in the [Auth::UserPass] needed to create an RPC [Client]
+    pub fn to_auth(self) -> Auth {
+        Auth::UserPass(self.user, self.password)
+    }
+}
+
+/// Error returned by [ConnectParams::get_cookie_values]
+#[derive(Debug)]
+pub enum CookieError {
+    /// The cookie file cannot be read
+    Io(std::io::Error),
+    /// The cookie file content, contained in the variant, isn't in the `user:password` format
+    MalformedContent(String),
+}
+
+impl fmt::Display for CookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieError::Io(e) => write!(f, "cannot read the cookie file: {}", e),
+            CookieError::MalformedContent(content) => {
+                write!(f, "malformed cookie file content: {:?}", content)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CookieError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CookieError::Io(e) => Some(e),
+            CookieError::MalformedContent(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CookieError {
+    fn from(e: std::io::Error) -> Self {
+        CookieError::Io(e)
+    }
+}
+
 impl ConnectParams {
     /// Parses the cookie file content
-    fn parse_cookie(content: String) -> Option<CookieValues> {
-        let values: Vec<_> = content.splitn(2, ':').collect();
-        let user = values.first()?.to_string();
-        let password = values.get(1)?.to_string();
-        Some(CookieValues { user, password })
+    fn parse_cookie(content: String) -> Result<CookieValues, CookieError> {
+        match content.split_once(':') {
+            Some((user, password)) => Ok(CookieValues {
+                user: user.to_string(),
+                password: password.to_string(),
+            }),
+            None => Err(CookieError::MalformedContent(content)),
+        }
     }
 
     /// Return the user and password values from cookie file
-    pub fn get_cookie_values(&self) -> Result<Option<CookieValues>, std::io::Error> {
+    pub fn get_cookie_values(&self) -> Result<CookieValues, CookieError> {
         let cookie = std::fs::read_to_string(&self.cookie_file)?;
-        Ok(self::ConnectParams::parse_cookie(cookie))
+        ConnectParams::parse_cookie(cookie)
+    }
+
+    /// Wait until the cookie file exists, polling every 100ms, so that a [Client] authenticated
+    /// with [Auth::CookieFile] can be created while the node is starting.
+    ///
+    /// Returns an error if the file doesn't appear within `timeout`
+    #[must_use = "the wait result should be checked, an error means the condition wasn't met in time"]
+    pub fn wait_for_cookie(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        while !self.cookie_file.exists() {
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!(
+                    "cookie file {:?} not created within {:?}",
+                    self.cookie_file,
+                    timeout
+                )
+                .into());
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        Ok(())
+    }
+
+    /// The [bitcoin::Network] corresponding to [ConnectParams::network], one of `main` (or empty),
+    /// `testnet`, `signet` and `regtest`, `None` for other networks
+    pub fn to_bitcoin_network(&self) -> Option<bitcoin::Network> {
+        network_from_name(&self.network)
+    }
+}
+
+impl fmt::Display for ConnectParams {
+    /// Compact summary of the sockets, eg. `rpc=127.0.0.1:18443 p2p=127.0.0.1:18444`, sockets
+    /// not enabled are omitted
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rpc={}", self.rpc_socket)?;
+        if let Some(socket) = self.p2p_socket {
+            write!(f, " p2p={}", socket)?;
+        }
+        if let Some(socket) = self.zmq_pub_raw_block_socket {
+            write!(f, " zmq_block={}", socket)?;
+        }
+        if let Some(socket) = self.zmq_pub_raw_tx_socket {
+            write!(f, " zmq_tx={}", socket)?;
+        }
+        Ok(())
     }
 }
 
@@ -103,6 +234,374 @@ pub enum P2P {
     Connect(SocketAddrV4, bool),
 }
 
+/// Identifies a connected peer, either by address or by the node id shown in `getpeerinfo`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PeerRef {
+    /// The peer address, like the `addr` field of `getpeerinfo`
+    Address(SocketAddrV4),
+    /// The peer node id, like the `id` field of `getpeerinfo`
+    Id(u64),
+}
+
+/// A descriptor to be imported with [BitcoinD::import_descriptors]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImportDescriptorRequest {
+    /// The descriptor to import, including the checksum
+    pub descriptor: String,
+    /// Creation time of the descriptor as UNIX epoch, blocks before this time are not rescanned
+    pub timestamp: u64,
+    /// The inclusive `(start, end)` range to import, required for ranged descriptors only
+    pub range: Option<(u32, u32)>,
+    /// If the descriptor has no private keys, the receiving wallet must have been created with
+    /// private keys disabled
+    pub watchonly: bool,
+}
+
+/// The active descriptors of a descriptor wallet, as returned by
+/// [BitcoinD::get_wallet_descriptor]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WalletDescriptors {
+    /// Descriptors deriving the receiving addresses, including the checksum
+    pub external: Vec<String>,
+    /// Descriptors deriving the change addresses, including the checksum
+    pub internal: Vec<String>,
+}
+
+/// An unspent output of a wallet, as returned by [BitcoinD::list_utxos]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Utxo {
+    /// Id of the transaction creating the output
+    pub txid: bitcoin::Txid,
+    /// Index of the output in the transaction
+    pub vout: u32,
+    /// Value of the output
+    pub amount: bitcoin::Amount,
+    /// Address of the output, if the script has an address form
+    pub address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>>,
+}
+
+/// Optional parameters of `sendtoaddress` used by [BitcoinD::send_to_address_with_opts],
+/// `Default` leaves every parameter to the bitcoind default
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SendToAddressOptions {
+    /// A comment stored in the wallet for the transaction
+    pub comment: Option<String>,
+    /// A comment stored in the wallet about the recipient
+    pub comment_to: Option<String>,
+    /// The fee is deducted from the sent amount
+    pub subtract_fee: Option<bool>,
+    /// Signal BIP125 replace-by-fee
+    pub replaceable: Option<bool>,
+    /// Confirmation target in blocks used to estimate the fee
+    pub confirmation_target: Option<u32>,
+    /// Fee estimation mode
+    pub estimate_mode: Option<bitcoincore_rpc::json::EstimateMode>,
+}
+
+/// A chain tip known by the node, as returned by [BitcoinD::get_chain_tips]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChainTip {
+    /// Height of the tip
+    pub height: u64,
+    /// Block hash of the tip
+    pub hash: bitcoin::BlockHash,
+    /// Number of blocks since the last block in common with the active chain, 0 for the active tip
+    pub branch_length: u32,
+    /// Validation status of the branch
+    pub status: ChainTipStatus,
+}
+
+/// Validation status of a [ChainTip]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChainTipStatus {
+    /// Tip of the active chain
+    Active,
+    /// Fully validated branch not part of the active chain
+    ValidFork,
+    /// All blocks are available but they were never fully validated
+    ValidHeaders,
+    /// Headers are valid but not all blocks are available
+    HeadersOnly,
+    /// The branch contains at least one invalid block
+    Invalid,
+}
+
+impl From<bitcoincore_rpc::json::GetChainTipsResultStatus> for ChainTipStatus {
+    fn from(status: bitcoincore_rpc::json::GetChainTipsResultStatus) -> Self {
+        use bitcoincore_rpc::json::GetChainTipsResultStatus::*;
+        match status {
+            Active => ChainTipStatus::Active,
+            ValidFork => ChainTipStatus::ValidFork,
+            ValidHeaders => ChainTipStatus::ValidHeaders,
+            HeadersOnly => ChainTipStatus::HeadersOnly,
+            Invalid => ChainTipStatus::Invalid,
+        }
+    }
+}
+
+/// An entry of the wallet history, as returned by [BitcoinD::list_transactions]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WalletTransaction {
+    /// Id of the transaction
+    pub txid: bitcoin::Txid,
+    /// Whether the entry sends or receives funds
+    pub category: TxCategory,
+    /// Amount sent, negative, or received by the wallet, excluding the fee
+    pub amount: bitcoin::SignedAmount,
+    /// Number of confirmations, negative if the transaction conflicts with the active chain
+    pub confirmations: i64,
+}
+
+/// Category of a [WalletTransaction]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TxCategory {
+    /// Funds sent to an address
+    Send,
+    /// Funds received by an address of the wallet
+    Receive,
+    /// Mature coinbase output
+    Generate,
+    /// Coinbase output not yet spendable
+    Immature,
+    /// Coinbase output of a block no longer in the active chain
+    Orphan,
+}
+
+impl From<bitcoincore_rpc::json::GetTransactionResultDetailCategory> for TxCategory {
+    fn from(category: bitcoincore_rpc::json::GetTransactionResultDetailCategory) -> Self {
+        use bitcoincore_rpc::json::GetTransactionResultDetailCategory::*;
+        match category {
+            Send => TxCategory::Send,
+            Receive => TxCategory::Receive,
+            Generate => TxCategory::Generate,
+            Immature => TxCategory::Immature,
+            Orphan => TxCategory::Orphan,
+        }
+    }
+}
+
+/// Validity and details of an address, as returned by [BitcoinD::validate_address]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AddressInfo {
+    /// Whether the address is valid for the node network
+    pub is_valid: bool,
+    /// The address, if valid
+    pub address: Option<String>,
+    /// Hex encoded script pubkey of the address, if valid
+    pub script_pub_key: Option<String>,
+    /// Whether the address belongs to the default wallet, if valid
+    pub is_mine: Option<bool>,
+    /// Whether the address is watch-only in the default wallet, if valid
+    pub is_watch_only: Option<bool>,
+}
+
+/// Decoded script, as returned by [BitcoinD::decode_script]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScriptInfo {
+    /// Script in assembly form
+    pub asm: String,
+    /// Script type, like `pubkeyhash`, `scripthash` or `nonstandard`
+    pub type_: String,
+    /// Addresses of the script, if any
+    pub addresses: Vec<String>,
+}
+
+/// Multisig address and its redeem script, as returned by [BitcoinD::create_multisig]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MultisigResult {
+    /// The multisig address
+    pub address: bitcoin::Address,
+    /// The script redeeming the address
+    pub redeem_script: bitcoin::ScriptBuf,
+}
+
+/// The amount of detail requested to [BitcoinD::raw_mempool]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MempoolQuery {
+    /// Only the txids, returned in [RawMempool::Txids]
+    Txids,
+    /// The txids with their [MempoolEntry], calling the verbose form of `getrawmempool`,
+    /// returned in [RawMempool::Entries]
+    Entries,
+}
+
+/// The mempool content, as returned by [BitcoinD::raw_mempool] with the detail of the
+/// [MempoolQuery]
+#[derive(Debug, PartialEq, Clone)]
+pub enum RawMempool {
+    /// The txids of the mempool transactions
+    Txids(Vec<bitcoin::Txid>),
+    /// The mempool transactions with their details
+    Entries(HashMap<bitcoin::Txid, MempoolEntry>),
+}
+
+impl RawMempool {
+    /// The txids of the mempool transactions, whatever the detail
+    pub fn txids(&self) -> Vec<bitcoin::Txid> {
+        match self {
+            RawMempool::Txids(txids) => txids.clone(),
+            RawMempool::Entries(entries) => entries.keys().cloned().collect(),
+        }
+    }
+}
+
+/// A mempool transaction, as returned by [BitcoinD::raw_mempool]
+#[derive(Debug, PartialEq, Clone)]
+pub struct MempoolEntry {
+    /// Fee paid by the transaction
+    pub fee: bitcoin::Amount,
+    /// Virtual size of the transaction
+    pub vsize: u64,
+    /// Weight of the transaction, computed from `vsize` before bitcoind 0.19
+    pub weight: u64,
+    /// Unconfirmed transactions spent by this transaction
+    pub depends: Vec<bitcoin::Txid>,
+    /// Fee rate in sat/vByte
+    pub fee_rate: f64,
+}
+
+/// Statistics of the UTXO set, as returned by [BitcoinD::txoutsetinfo]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TxOutSetInfo {
+    /// Height of the block the statistics refer to
+    pub height: u64,
+    /// Hash of the block the statistics refer to
+    pub best_block: bitcoin::BlockHash,
+    /// Number of transactions with unspent outputs
+    pub transactions: u64,
+    /// Number of unspent outputs
+    pub txouts: u64,
+    /// Total value of the unspent outputs
+    pub total_amount: bitcoin::Amount,
+}
+
+/// Network state of the node, as returned by [BitcoinD::network_info]
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct NetworkInfo {
+    /// Node version, eg. `250000` for 25.0
+    pub version: u32,
+    /// Node user agent, eg. `/Satoshi:25.0.0/`
+    pub subversion: String,
+    /// Number of connections, inbound and outbound
+    pub connections: u32,
+    /// Whether p2p networking is enabled
+    #[serde(rename = "networkactive")]
+    pub network_active: bool,
+    /// Minimum relay fee rate in BTC/kvB
+    #[serde(rename = "relayfee")]
+    pub relay_fee: f64,
+}
+
+/// Wallet state, as returned by [BitcoinD::wallet_info]
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct WalletInfo {
+    /// Wallet name, `default` for [BitcoinD::client]
+    #[serde(rename = "walletname")]
+    pub wallet_name: String,
+    /// Wallet version
+    #[serde(rename = "walletversion")]
+    pub wallet_version: u32,
+    /// Database format, `bdb` or `sqlite`, empty before 0.21
+    #[serde(default)]
+    pub format: String,
+    /// Trusted balance
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    pub balance: bitcoin::Amount,
+    /// Balance of untrusted unconfirmed transactions
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    pub unconfirmed_balance: bitcoin::Amount,
+    /// Balance of immature coinbase outputs
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    pub immature_balance: bitcoin::Amount,
+    /// Progress of the ongoing rescan, `None` if the wallet isn't scanning
+    #[serde(default, deserialize_with = "deserialize_scanning")]
+    pub scanning: Option<ScanProgress>,
+}
+
+/// Balances of the default wallet by category, as returned by [BitcoinD::get_balance_details]
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct BalanceDetails {
+    /// Confirmed balance and unconfirmed balance from outputs created by the wallet
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    pub trusted: bitcoin::Amount,
+    /// Balance of unconfirmed transactions not created by the wallet
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    pub untrusted_pending: bitcoin::Amount,
+    /// Balance of immature coinbase outputs
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    pub immature: bitcoin::Amount,
+    /// Balance of outputs to already used addresses, `None` unless the wallet has the
+    /// `avoid_reuse` flag
+    #[serde(default, with = "bitcoin::amount::serde::as_btc::opt")]
+    pub used: Option<bitcoin::Amount>,
+}
+
+/// Progress of a wallet rescan, see [WalletInfo::scanning]
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct ScanProgress {
+    /// Seconds elapsed since the rescan started
+    pub duration: u64,
+    /// Rescan progress between 0.0 and 1.0
+    pub progress: f64,
+}
+
+/// `scanning` is `false` when the wallet isn't scanning, or missing in versions before 0.19
+fn deserialize_scanning<'de, D>(deserializer: D) -> Result<Option<ScanProgress>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    // an object while scanning, `false` otherwise
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).ok())
+}
+
+/// Mining state of the node, as returned by [BitcoinD::mining_info]
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct MiningInfo {
+    /// Current block height
+    pub blocks: u64,
+    /// Current difficulty
+    pub difficulty: f64,
+    /// Estimated network hashes per second
+    #[serde(rename = "networkhashps")]
+    pub network_hash_ps: f64,
+    /// Network name, eg. `regtest`
+    pub chain: String,
+}
+
+/// RPC server state, as returned by [BitcoinD::rpc_info]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RpcInfo {
+    /// Methods of the RPC calls in progress, excluding the `getrpcinfo` call itself
+    pub active_calls: Vec<String>,
+}
+
+/// Statistics of a block, as returned by [BitcoinD::get_block_stats]
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct BlockStats {
+    /// Height of the block
+    pub height: u64,
+    /// Average fee rate in sat/vByte
+    #[serde(rename = "avgfeerate")]
+    pub avg_fee_rate: u64,
+    /// Maximum fee rate in sat/vByte
+    #[serde(rename = "maxfeerate")]
+    pub max_fee_rate: u64,
+    /// Minimum fee rate in sat/vByte
+    #[serde(rename = "minfeerate")]
+    pub min_fee_rate: u64,
+    /// Number of transactions, including the coinbase
+    pub txs: u32,
+    /// Total size in bytes of the transactions, excluding the coinbase
+    pub total_size: u64,
+    /// Change in the size of the UTXO set in bytes
+    pub utxo_size_inc: i64,
+}
+
+/// Result type returned by this crate, [anyhow::Result] can be obtained with `?` since [Error]
+/// implements [std::error::Error]
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
 /// All the possible error in this crate
 pub enum Error {
     /// Wrapper of io Error
@@ -125,6 +624,33 @@ pub enum Error {
     RpcUserAndPasswordUsed,
     /// Returned when expecting an auto-downloaded executable but `BITCOIND_SKIP_DOWNLOAD` env var is set
     SkipDownload,
+    /// Returned when the requested version isn't one of [versions::SUPPORTED_VERSIONS]
+    UnsupportedVersion(String),
+    /// Returned when launching electrs but no executable is found
+    /// (no `ELECTRS_EXE`, no `electrs` in `PATH`)
+    NoElectrsExecutableFound,
+    /// Returned when [Conf::dust_relay_fee] is not between 0.0 and 10.0 sat/vByte
+    InvalidDustRelayFee(f64),
+    /// Returned when an arg in [Conf::args] is also set by a dedicated [Conf] field
+    ConflictingArg(&'static str),
+    /// Returned when the process cannot be spawned, contains the executable path and the spawn
+    /// error, whose kind tells for example if the executable isn't found or isn't executable
+    ProcessSpawnFailed(String, std::io::Error),
+    /// Returned when creating or loading a wallet fails, contains the wallet name
+    WalletError {
+        /// Name of the wallet
+        wallet: String,
+        /// Error returned by the node
+        inner: bitcoincore_rpc::Error,
+    },
+    /// Returned when a local port cannot be booked, the port is 0 when any port was requested
+    /// like in [get_available_port]
+    PortUnavailable(u16),
+    /// Returned when calling wallet methods on a node started with [Conf::no_wallet]
+    NoWallet,
+    /// Wrapper of any other error, allows `?` on [anyhow::Result] in functions returning this
+    /// error
+    Other(anyhow::Error),
 }
 
 impl fmt::Debug for Error {
@@ -134,11 +660,21 @@ impl fmt::Debug for Error {
             Error::Rpc(_) => write!(f, "bitcoin_rpc::Error"),
             Error::NoFeature => write!(f, "Called a method requiring a feature to be set, but it's not"),
             Error::NoEnvVar => write!(f, "Called a method requiring env var `BITCOIND_EXE` to be set, but it's not"),
-            Error::NoBitcoindExecutableFound =>  write!(f, "`bitcoind` executable is required, provide it with one of the following: set env var `BITCOIND_EXE` or use a feature like \"22_1\" or have `bitcoind` executable in the `PATH`"),
+            Error::NoBitcoindExecutableFound =>  write!(f, "`bitcoind` executable is required, provide it with one of the following: set env var `BITCOIND_EXE` or `BITCOIND_EXE_DIR` or use a feature like \"22_1\" or have `bitcoind` executable in the `PATH`"),
             Error::EarlyExit(e) => write!(f, "The bitcoind process terminated early with exit code {}", e),
             Error::BothDirsSpecified => write!(f, "tempdir and staticdir cannot be enabled at same time in configuration options"),
             Error::RpcUserAndPasswordUsed => write!(f, "`-rpcuser` and `-rpcpassword` cannot be used, it will be deprecated soon and it's recommended to use `-rpcauth` instead which works alongside with the default cookie authentication"),
             Error::SkipDownload => write!(f, "expecting an auto-downloaded executable but `BITCOIND_SKIP_DOWNLOAD` env var is set"),
+            Error::UnsupportedVersion(version) => write!(f, "bitcoind version {:?} is not supported", version),
+            Error::NoElectrsExecutableFound => write!(f, "`electrs` executable is required, provide it with one of the following: set env var `ELECTRS_EXE` or have `electrs` executable in the `PATH`"),
+            Error::InvalidDustRelayFee(rate) => write!(f, "dust relay fee {} sat/vByte is not between 0.0 and 10.0", rate),
+            Error::ConflictingArg(arg) => write!(f, "`{}` cannot be in `Conf::args` since it's set by a dedicated `Conf` field", arg),
+            Error::ProcessSpawnFailed(exe, e) => write!(f, "Error while executing {:?}: {}", exe, e),
+            Error::WalletError { wallet, .. } => write!(f, "cannot create or load wallet {:?}", wallet),
+            Error::PortUnavailable(0) => write!(f, "cannot book any local port"),
+            Error::PortUnavailable(port) => write!(f, "local port {} is unavailable", port),
+            Error::NoWallet => write!(f, "Called a wallet method on a node started with `Conf::no_wallet`"),
+            Error::Other(e) => write!(f, "{}", e),
         }
     }
 }
@@ -154,6 +690,9 @@ impl std::error::Error for Error {
         match self {
             Error::Io(e) => Some(e),
             Error::Rpc(e) => Some(e),
+            Error::ProcessSpawnFailed(_, e) => Some(e),
+            Error::WalletError { inner, .. } => Some(inner),
+            Error::Other(e) => Some(e.as_ref()),
             _ => None,
         }
     }
@@ -183,7 +722,7 @@ const INVALID_ARGS: [&str; 2] = ["-rpcuser", "-rpcpassword"];
 /// ```
 ///
 #[non_exhaustive]
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Conf<'a> {
     /// Bitcoind command line arguments containing no spaces like `vec!["-dbcache=300", "-regtest"]`
     /// note that `port`, `rpcport`, `connect`, `datadir`, `listen`
@@ -225,8 +764,100 @@ pub struct Conf<'a> {
     /// are returned reducing the probability of conflicts to negligible.
     pub attempts: u8,
 
-    /// Enable the ZMQ interface to be accessible.
+    /// Enable the ZMQ interface to be accessible, with the `zmq` feature notifications can be
+    /// received with the subscribers in the [zmq] module.
     pub enable_zmq: bool,
+
+    /// Enable the REST interface, accessible with [rest::RestClient] at [BitcoinD::rest_url]
+    pub enable_rest: bool,
+
+    /// Relay and mine non-standard transactions like bare multisig or oversized `OP_RETURN`
+    /// outputs, adds `-acceptnonstdtxn=1`, or `-acceptnonstdtxn=0` when `Some(false)`, which must
+    /// not be in [Conf::args] too.
+    ///
+    /// Defaults to `None`, the arg isn't added and Bitcoin Core default applies, requiring
+    /// standard transactions on every network, an `-acceptnonstdtxn` in [Conf::args] is left as
+    /// is. The option is refused on mainnet. It only affects relay policy, non-standard
+    /// transactions are still valid by consensus and can be mined by other means.
+    pub accept_non_std_txns: Option<bool>,
+
+    /// Dust relay fee rate in sat/vByte, outputs worth less than the fee needed to spend them at
+    /// this rate are considered dust and not relayed. Adds `-dustrelayfee` converted to BTC/kvB.
+    ///
+    /// Must be between 0.0 and 10.0 and `-dustrelayfee` must not be in [Conf::args] too.
+    /// Defaults to `None`, meaning Bitcoin Core default of 3 sat/vByte.
+    pub dust_relay_fee: Option<f64>,
+
+    /// Minimum fee rate in BTC/kvB for transactions to be relayed and mined, adds
+    /// `-minrelaytxfee`, which must not be in [Conf::args] too.
+    ///
+    /// Defaults to `None`, meaning Bitcoin Core default of 0.00001 BTC/kvB.
+    pub min_relay_tx_fee: Option<f64>,
+
+    /// Maximum mempool memory usage in megabytes, adds `-maxmempool`, which must not be in
+    /// [Conf::args] too. When full the lowest fee rate transactions are evicted and the mempool
+    /// minimum fee rises.
+    ///
+    /// Bitcoin Core refuses values lower than 5. Defaults to `None`, meaning Bitcoin Core default
+    /// of 300 MB.
+    pub mempool_size_mb: Option<u32>,
+
+    /// Path of the cookie file, overriding the default `<work_dir>/<network>/.cookie`, for example
+    /// to share it with clients started before the node. Adds `-rpccookiefile`, which must not be
+    /// in [Conf::args] too, and is used in [ConnectParams::cookie_file] and by the RPC client.
+    pub cookie_path: Option<PathBuf>,
+
+    /// Skip the creation or loading of the `default` wallet, for relay only or pruned nodes not
+    /// needing wallet functionality. [BitcoinD::client] is then connected to the node URL without
+    /// wallet and [BitcoinD::create_wallet] returns [Error::NoWallet].
+    pub no_wallet: bool,
+
+    /// Block assumed to have valid scripts, with its ancestors, adds `-assumevalid`, which must not
+    /// be in [Conf::args] too. Useful to simulate an IBD skipping script validation.
+    ///
+    /// `BlockHash::all_zeros()` maps to `-assumevalid=0`, disabling the assumption.
+    /// Defaults to `None`, meaning Bitcoin Core default, which on regtest is no assumption.
+    pub assume_valid: Option<bitcoin::BlockHash>,
+
+    /// Minimum cumulative chain work, as the hex string expected by Bitcoin Core, below which the
+    /// node considers itself in IBD and doesn't trust the chain. Adds `-minimumchainwork`, which
+    /// must not be in [Conf::args] too.
+    ///
+    /// `"0x00"` disables the check. Defaults to `None`, meaning Bitcoin Core default, which on
+    /// regtest is no minimum.
+    pub min_chain_work: Option<String>,
+
+    /// Run the node in blocks-only mode, adding `-blocksonly` which must not be in [Conf::args]
+    /// too, to test block propagation without transaction relay.
+    ///
+    /// Transactions received from peers are rejected and the node doesn't announce its own, so
+    /// transactions broadcast to it with `sendrawtransaction` stay in its mempool and are not
+    /// propagated. Defaults to `false`.
+    pub blocksonly: bool,
+
+    /// Rebuild the block index and the chain state from the block files on startup, adding
+    /// `-reindex` which must not be in [Conf::args] too. Useful with a persistent
+    /// [Conf::staticdir] to test reindexing or the rebuild of indexes like `-txindex`.
+    ///
+    /// There is no startup timeout to extend: the launch waits until the node accepts RPC calls
+    /// however long it takes, but the blocks are imported in the background afterwards, so wait
+    /// for the expected height before relying on the chain. Defaults to `false`.
+    pub reindex: bool,
+
+    /// Peers to connect to on startup, adding an `-addnode` arg for each of them. Unlike
+    /// [P2P::Connect], the node also connects to other peers and accepts inbound connections, so
+    /// [P2P::No] is treated as [P2P::Yes] when this isn't empty.
+    ///
+    /// Note `-connect` disables the other outbound connections, so with [P2P::Connect] only the
+    /// connect peer is used. Defaults to no peers.
+    pub addnode: Vec<SocketAddrV4>,
+
+    /// Blocks mined to an address of the default wallet before the launch returns, so that tests
+    /// can declare their starting state, eg. `101` gives a spendable coinbase of 50 BTC.
+    ///
+    /// Incompatible with [Conf::no_wallet], the launch returns [Error::NoWallet]. Note with a
+    /// persistent [Conf::staticdir] the blocks are mined again on every launch. Defaults to `0`.
+    pub initial_blocks: u64,
 }
 
 impl Default for Conf<'_> {
@@ -240,176 +871,235 @@ impl Default for Conf<'_> {
             staticdir: None,
             attempts: 3,
             enable_zmq: false,
+            enable_rest: false,
+            accept_non_std_txns: None,
+            dust_relay_fee: None,
+            min_relay_tx_fee: None,
+            mempool_size_mb: None,
+            cookie_path: None,
+            no_wallet: false,
+            assume_valid: None,
+            min_chain_work: None,
+            blocksonly: false,
+            reindex: false,
+            addnode: vec![],
+            initial_blocks: 0,
+        }
+    }
+}
+
+impl Conf<'_> {
+    /// Serialize [Conf::args] and [Conf::network] in the `bitcoin.conf` file format.
+    ///
+    /// The network selection args are written at the top while the others are written in the
+    /// `[network]` section, otherwise bitcoind ignores options like `port` on non-main networks.
+    /// Flags without value are written with value `1`, eg. `-txindex` becomes `txindex=1`
+    pub fn to_conf_file(&self) -> String {
+        let (network_args, other_args): (Vec<&str>, Vec<&str>) =
+            self.args.iter().partition(|arg| is_network_arg(arg));
+        let mut lines: Vec<String> = network_args.into_iter().map(conf_file_line).collect();
+        if !self.network.is_empty() && !other_args.is_empty() {
+            lines.push(format!("[{}]", self.network));
+        }
+        lines.extend(other_args.into_iter().map(conf_file_line));
+        lines.iter().map(|l| format!("{}\n", l)).collect()
+    }
+}
+
+/// The args and network parsed from a `bitcoin.conf` file, owned since [Conf] borrows them,
+/// use [ConfFile::conf] to obtain the [Conf] to launch a node with
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ConfFile {
+    /// The parsed args, in the same format of [Conf::args], eg. `-txindex=1`
+    pub args: Vec<String>,
+
+    /// The parsed network, in the same format of [Conf::network]
+    pub network: String,
+}
+
+impl ConfFile {
+    /// Parse a `bitcoin.conf` file as written by [Conf::to_conf_file]
+    pub fn read(path: &Path) -> Result<ConfFile> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("cannot read conf file {:?}", path))?;
+        let mut args = vec![];
+        let mut network = String::new();
+        let mut in_section = false;
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let section = &line[1..line.len() - 1];
+                network = if section == "main" { "" } else { section }.to_string();
+                in_section = true;
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .with_context(|| format!("parse error on line {}: {}", i + 1, line))?;
+            let (key, value) = (key.trim(), value.trim());
+            let arg = if !in_section && NETWORK_FLAGS.contains(&key) && value == "1" {
+                network = key.to_string();
+                format!("-{}", key)
+            } else {
+                if !in_section && key == "chain" {
+                    network = value.to_string();
+                }
+                format!("-{}={}", key, value)
+            };
+            args.push(arg);
+        }
+        Ok(ConfFile { args, network })
+    }
+
+    /// A [Conf] borrowing the parsed `args` and `network`, with default values for everything else
+    pub fn conf(&self) -> Conf<'_> {
+        Conf {
+            args: self.args.iter().map(String::as_str).collect(),
+            network: &self.network,
+            ..Conf::default()
         }
     }
 }
 
+const NETWORK_FLAGS: [&str; 3] = ["regtest", "testnet", "signet"];
+
+/// Whether the arg selects the network, like `-regtest` or `-chain=signet`
+fn is_network_arg(arg: &str) -> bool {
+    let arg = arg.trim_start_matches('-');
+    NETWORK_FLAGS.contains(&arg) || arg.starts_with("chain=")
+}
+
+/// Convert a command line arg in a `bitcoin.conf` line
+fn conf_file_line(arg: &str) -> String {
+    let arg = arg.trim_start_matches('-');
+    if arg.contains('=') {
+        arg.to_string()
+    } else {
+        format!("{}=1", arg)
+    }
+}
+
 impl BitcoinD {
     /// Launch the bitcoind process from the given `exe` executable with default args.
     ///
     /// Waits for the node to be ready to accept connections before returning
-    pub fn new<S: AsRef<OsStr>>(exe: S) -> anyhow::Result<BitcoinD> {
+    pub fn new<S: AsRef<OsStr>>(exe: S) -> Result<BitcoinD> {
         BitcoinD::with_conf(exe, &Conf::default())
     }
 
-    /// Launch the bitcoind process from the given `exe` executable with given [Conf] param
-    pub fn with_conf<S: AsRef<OsStr>>(exe: S, conf: &Conf) -> anyhow::Result<BitcoinD> {
-        let tmpdir = conf
-            .tmpdir
-            .clone()
-            .or_else(|| env::var("TEMPDIR_ROOT").map(PathBuf::from).ok());
-        let work_dir = match (&tmpdir, &conf.staticdir) {
-            (Some(_), Some(_)) => return Err(Error::BothDirsSpecified.into()),
-            (Some(tmpdir), None) => DataDir::Temporary(TempDir::new_in(tmpdir)?),
-            (None, Some(workdir)) => {
-                fs::create_dir_all(workdir)?;
-                DataDir::Persistent(workdir.to_owned())
-            }
-            (None, None) => DataDir::Temporary(TempDir::new()?),
+    /// Launch a node, found with [exe_path], ready to back a Lightning Network daemon in tests.
+    ///
+    /// The ZMQ interface is enabled (`-zmqpubrawblock` and `-zmqpubrawtx`, see [ConnectParams]),
+    /// `-deprecatedrpc=create_bdb` is set for Core-Lightning versions still creating legacy wallets
+    /// and 101 blocks are mined to the default wallet so that it has a spendable coinbase.
+    ///
+    /// Known to work with `lnd` 0.15 and later, `core-lightning` 23.x and later and `ldk-node`
+    /// using the RPC chain source.
+    pub fn for_lightning() -> Result<BitcoinD> {
+        let mut conf = Conf {
+            enable_zmq: true,
+            initial_blocks: 101,
+            ..Conf::default()
         };
+        conf.args.push("-deprecatedrpc=create_bdb");
+        BitcoinD::with_conf(exe_path()?, &conf)
+    }
 
-        let work_dir_path = work_dir.path();
-        debug!("work_dir: {:?}", work_dir_path);
-        let cookie_file = work_dir_path.join(conf.network).join(".cookie");
-        let rpc_port = get_available_port()?;
-        let rpc_socket = SocketAddrV4::new(LOCAL_IP, rpc_port);
-        let rpc_url = format!("http://{}", rpc_socket);
-        let (p2p_args, p2p_socket) = match conf.p2p {
-            P2P::No => (vec!["-listen=0".to_string()], None),
-            P2P::Yes => {
-                let p2p_port = get_available_port()?;
-                let p2p_socket = SocketAddrV4::new(LOCAL_IP, p2p_port);
-                let p2p_arg = format!("-port={}", p2p_port);
-                let args = vec![p2p_arg];
-                (args, Some(p2p_socket))
-            }
-            P2P::Connect(other_node_url, listen) => {
-                let p2p_port = get_available_port()?;
-                let p2p_socket = SocketAddrV4::new(LOCAL_IP, p2p_port);
-                let p2p_arg = format!("-port={}", p2p_port);
-                let connect = format!("-connect={}", other_node_url);
-                let mut args = vec![p2p_arg, connect];
-                if listen {
-                    args.push("-listen=1".to_string())
-                }
-                (args, Some(p2p_socket))
-            }
-        };
+    /// Launch the bitcoind process from the given `exe` executable with given [Conf] param
+    pub fn with_conf<S: AsRef<OsStr>>(exe: S, conf: &Conf) -> Result<BitcoinD> {
+        BitcoinD::launch(exe, conf, None)
+    }
 
-        let (zmq_args, zmq_pub_raw_tx_socket, zmq_pub_raw_block_socket) = match conf.enable_zmq {
-            true => {
-                let zmq_pub_raw_tx_port = get_available_port()?;
-                let zmq_pub_raw_tx_socket = SocketAddrV4::new(LOCAL_IP, zmq_pub_raw_tx_port);
-                let zmq_pub_raw_block_port = get_available_port()?;
-                let zmq_pub_raw_block_socket = SocketAddrV4::new(LOCAL_IP, zmq_pub_raw_block_port);
-                let zmqpubrawblock_arg =
-                    format!("-zmqpubrawblock=tcp://0.0.0.0:{}", zmq_pub_raw_block_port);
-                let zmqpubrawtx_arg = format!("-zmqpubrawtx=tcp://0.0.0.0:{}", zmq_pub_raw_tx_port);
-                (
-                    vec![zmqpubrawtx_arg, zmqpubrawblock_arg],
-                    Some(zmq_pub_raw_tx_socket),
-                    Some(zmq_pub_raw_block_socket),
-                )
-            }
-            false => (vec![], None, None),
-        };
+    /// Launch a node, found with [exe_path], whose data directory is initialized with a copy of
+    /// the pre-built chain state at `snapshot_path`, avoiding the IBD of a realistic chain.
+    ///
+    /// The snapshot is the network directory of a stopped node, eg. `<datadir>/regtest`, in the
+    /// native Bitcoin Core layout with the `blocks` and `chainstate` directories, it must match
+    /// [Conf::network]. It's copied rather than hard-linked since the node appends to the block
+    /// files, and bitcoind verifies the last blocks at startup. A shared snapshot can be
+    /// specified in the `BITCOIND_SNAPSHOT` env var, see [snapshot_path].
+    pub fn with_snapshot(snapshot_path: &Path, conf: &Conf) -> Result<BitcoinD> {
+        if !snapshot_path.join("chainstate").is_dir() {
+            return Err(anyhow::anyhow!(
+                "{:?} is not a snapshot, the chainstate directory is missing",
+                snapshot_path
+            )
+            .into());
+        }
+        BitcoinD::launch(exe_path()?, conf, Some(snapshot_path))
+    }
 
-        let stdout = if conf.view_stdout {
-            Stdio::inherit()
-        } else {
-            Stdio::null()
-        };
+    /// Launch `exe` with `conf`, copying `snapshot` in the network directory before spawning
+    fn launch<S: AsRef<OsStr>>(exe: S, conf: &Conf, snapshot: Option<&Path>) -> Result<BitcoinD> {
+        let (launch, process, client) = Launch::start(exe.as_ref(), conf, snapshot)?;
+        let bitcoind = BitcoinD::started(conf, launch, Process::Std(process), client);
+        // built before mining so that the process is killed on errors
+        mine_initial_blocks(&bitcoind.client, conf.initial_blocks)?;
+        Ok(bitcoind)
+    }
 
-        let datadir_arg = format!("-datadir={}", work_dir_path.display());
-        let rpc_arg = format!("-rpcport={}", rpc_port);
-        let default_args = [&datadir_arg, &rpc_arg];
-        let conf_args = validate_args(conf.args.clone())?;
+    /// Build the [BitcoinD] of a node started with `conf`
+    fn started(conf: &Conf, launch: Launch, process: Process, client: Client) -> BitcoinD {
+        BitcoinD {
+            process,
+            client,
+            work_dir: launch.work_dir,
+            params: launch.params,
+            network: conf.network.to_owned(),
+            stop_called: false,
+            no_wallet: conf.no_wallet,
+            fallback_fee: launch.fallback_fee,
+        }
+    }
 
-        debug!(
-            "launching {:?} with args: {:?} {:?} AND custom args: {:?}",
-            exe.as_ref(),
-            default_args,
-            p2p_args,
-            conf_args
-        );
+    #[cfg(feature = "tokio")]
+    /// Async version of [BitcoinD::with_conf], the process is spawned with
+    /// [tokio::process::Command] and the startup checks don't block the async runtime.
+    ///
+    /// Only startup is async, use [BitcoinD::stop_async] to stop the node without blocking.
+    pub async fn with_conf_async<S: AsRef<OsStr>>(exe: S, conf: &Conf<'_>) -> Result<BitcoinD> {
+        let (launch, process, client) = Launch::start_async(exe.as_ref(), conf).await?;
+        let client = mine_initial_blocks_async(client, conf.initial_blocks).await?;
+        Ok(BitcoinD::started(
+            conf,
+            launch,
+            Process::Tokio(process),
+            client,
+        ))
+    }
 
-        let mut process = Command::new(exe.as_ref())
-            .args(default_args)
-            .args(&p2p_args)
-            .args(&conf_args)
-            .args(&zmq_args)
-            .stdout(stdout)
-            .spawn()
-            .with_context(|| format!("Error while executing {:?}", exe.as_ref()))?;
-
-        let node_url_default = format!("{}/wallet/default", rpc_url);
-        let mut i = 0;
-        // wait bitcoind is ready, use default wallet
-        let client = loop {
-            if let Some(status) = process.try_wait()? {
-                if conf.attempts > 0 {
-                    warn!("early exit with: {:?}. Trying to launch again ({} attempts remaining), maybe some other process used our available port", status, conf.attempts);
-                    let mut conf = conf.clone();
-                    conf.attempts -= 1;
-                    return Self::with_conf(exe, &conf)
-                        .with_context(|| format!("Remaining attempts {}", conf.attempts));
-                } else {
-                    error!("early exit with: {:?}", status);
-                    return Err(Error::EarlyExit(status).into());
-                }
-            }
-            thread::sleep(Duration::from_millis(100));
-            assert!(process.stderr.is_none());
-            let client_result = Client::new(&rpc_url, Auth::CookieFile(cookie_file.clone()));
-
-            if let Ok(client_base) = client_result {
-                // RpcApi has get_blockchain_info method, however being generic with `Value` allows
-                // to be compatible with different version, in the end we are only interested if
-                // the call is succesfull not in the returned value.
-                if client_base.call::<Value>("getblockchaininfo", &[]).is_ok() {
-                    // Try creating new wallet, if fails due to already existing wallet file
-                    // try loading the same. Return if still errors.
-                    if client_base
-                        .create_wallet("default", None, None, None, None)
-                        .is_err()
-                    {
-                        client_base.load_wallet("default")?;
-                    }
-                    break Client::new(&node_url_default, Auth::CookieFile(cookie_file.clone()))?;
-                }
-            }
-
-            debug!(
-                "bitcoin client for process {} not ready ({})",
-                process.id(),
-                i
-            );
-
-            i += 1;
-        };
-
-        Ok(BitcoinD {
-            process,
-            client,
-            work_dir,
-            params: ConnectParams {
-                cookie_file,
-                rpc_socket,
-                p2p_socket,
-                zmq_pub_raw_block_socket,
-                zmq_pub_raw_tx_socket,
-            },
-        })
-    }
+    #[cfg(feature = "tarball")]
+    /// Launch the bitcoind executable contained in the bitcoin core release tarball at
+    /// `tarball_path` with the given [Conf], useful in environments without network access.
+    ///
+    /// Only `.tar.gz` tarballs with the original release filename, eg.
+    /// `bitcoin-25.1-x86_64-linux-gnu.tar.gz`, of the [versions::SUPPORTED_VERSIONS] are accepted
+    /// since their hash is verified against the bundled sums. The executable is extracted once in
+    /// the OS temporary directory and reused by following calls
+    pub fn from_tarball(tarball_path: &Path, conf: &Conf) -> Result<BitcoinD> {
+        BitcoinD::with_conf(tarball::extract_bitcoind(tarball_path)?, conf)
+    }
 
     /// Returns the rpc URL including the schema eg. http://127.0.0.1:44842
     pub fn rpc_url(&self) -> String {
         format!("http://{}", self.params.rpc_socket)
     }
 
+    /// Returns the REST URL including the schema eg. http://127.0.0.1:44842/rest, the REST
+    /// interface must be enabled with [Conf::enable_rest]
+    pub fn rest_url(&self) -> String {
+        format!("http://{}/rest", self.params.rpc_socket)
+    }
+
     #[cfg(any(feature = "0_19_1", not(feature = "download")))]
     /// Returns the rpc URL including the schema and the given `wallet_name`
     /// eg. http://127.0.0.1:44842/wallet/my_wallet
+    ///
+    /// On a node started with [Conf::no_wallet] the URL is returned anyway, calls to it fail
+    /// unless the wallet has been created with the RPC client
     pub fn rpc_url_with_wallet<T: AsRef<str>>(&self, wallet_name: T) -> String {
         format!(
             "http://{}/wallet/{}",
@@ -418,332 +1108,4139 @@ impl BitcoinD {
         )
     }
 
+    /// Create a new [Client] for this node, equivalent to [BitcoinD::client] and using the default
+    /// wallet unless [Conf::no_wallet], so that it can be moved to another thread or task while
+    /// this struct keeps owning the process
+    pub fn client_clone(&self) -> Result<Client> {
+        let url = if self.no_wallet {
+            self.rpc_url()
+        } else {
+            format!("{}/wallet/default", self.rpc_url())
+        };
+        Ok(Client::new(
+            &url,
+            Auth::CookieFile(self.params.cookie_file.clone()),
+        )?)
+    }
+
     /// Return the current workdir path of the running node
     pub fn workdir(&self) -> PathBuf {
         self.work_dir.path()
     }
 
+    /// Returns the path of the Bitcoin Core debug log, useful to inspect it after a failure
+    pub fn debug_log_path(&self) -> PathBuf {
+        self.work_dir.path().join(&self.network).join("debug.log")
+    }
+
+    /// Returns a new address of the default wallet, checked against the network of the node as
+    /// specified in [Conf::network]
+    pub fn get_receive_address(&self) -> Result<bitcoin::Address> {
+        let network = self
+            .params
+            .to_bitcoin_network()
+            .with_context(|| format!("unknown network {:?}", self.network))?;
+        let address = self
+            .client
+            .get_new_address(None, None)?
+            .require_network(network)
+            .with_context(|| format!("address not valid for network {}", network))?;
+        Ok(address)
+    }
+
+    /// Mine `blocks` blocks to an address of the default wallet, advancing the node mocktime by
+    /// `interval_secs` before each block to simulate realistic block timing, eg. for fee
+    /// estimation or timelock tests.
+    ///
+    /// Mocktime starts from the current time or the tip time, whichever is later, and it stays set
+    /// at the time of the last block, so that following calls continue from there. The node must
+    /// not rely on a mocktime set otherwise.
+    pub fn generate_blocks_with_interval(
+        &self,
+        blocks: u64,
+        interval_secs: u64,
+    ) -> Result<Vec<bitcoin::BlockHash>> {
+        let address = self.get_receive_address()?;
+        let tip = self.client.get_best_block_hash()?;
+        let tip_time = self.client.get_block_header_info(&tip)?.time as u64;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("system time before unix epoch")?
+            .as_secs();
+        let mut mocktime = now.max(tip_time);
+        let mut hashes = Vec::with_capacity(blocks as usize);
+        for _ in 0..blocks {
+            mocktime += interval_secs;
+            self.client
+                .call::<Value>("setmocktime", &[json!(mocktime)])?;
+            hashes.extend(self.client.generate_to_address(1, &address)?);
+        }
+        Ok(hashes)
+    }
+
     /// Returns the [P2P] enum to connect to this node p2p port
     pub fn p2p_connect(&self, listen: bool) -> Option<P2P> {
         self.params.p2p_socket.map(|s| P2P::Connect(s, listen))
     }
 
+    /// Returns the p2p address of this node eg. `127.0.0.1:18444`, to configure components
+    /// started outside this crate, or `None` if started with [P2P::No]
+    pub fn node_address(&self) -> Option<String> {
+        self.params.p2p_socket.map(|s| s.to_string())
+    }
+
     /// Stop the node, waiting correct process termination
-    pub fn stop(&mut self) -> anyhow::Result<ExitStatus> {
+    #[must_use = "the stop result should be checked to ensure a clean shutdown"]
+    pub fn stop(&mut self) -> Result<ExitStatus> {
+        self.stop_called = true;
         self.client.stop()?;
         Ok(self.process.wait()?)
     }
 
+    /// Stop the node like [BitcoinD::stop] unless the process already exited, eg. because it
+    /// crashed, in which case the `stop` RPC is skipped and the exit status is returned.
+    ///
+    /// Returns `None` if the node was running and has been stopped, useful in teardown logic
+    /// which must not fail on crashed nodes
+    #[must_use = "the stop result should be checked to ensure a clean shutdown"]
+    pub fn try_stop(&mut self) -> Result<Option<ExitStatus>> {
+        self.stop_called = true;
+        if let Some(status) = self.process.try_wait()? {
+            return Ok(Some(status));
+        }
+        self.stop()?;
+        Ok(None)
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Async version of [BitcoinD::stop], polling process termination with [tokio::time::sleep]
+    #[must_use = "the stop result should be checked to ensure a clean shutdown"]
+    pub async fn stop_async(&mut self) -> Result<ExitStatus> {
+        self.stop_called = true;
+        self.client.stop()?;
+        loop {
+            if let Some(status) = self.process.try_wait()? {
+                return Ok(status);
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
     #[cfg(any(feature = "0_19_1", not(feature = "download")))]
     /// Create a new wallet in the running node, and return an RPC client connected to the just
     /// created wallet
-    pub fn create_wallet<T: AsRef<str>>(&self, wallet: T) -> anyhow::Result<Client> {
+    pub fn create_wallet<T: AsRef<str>>(&self, wallet: T) -> Result<Client> {
+        if self.no_wallet {
+            return Err(Error::NoWallet);
+        }
         let _ = self
             .client
-            .create_wallet(wallet.as_ref(), None, None, None, None)?;
+            .create_wallet(wallet.as_ref(), None, None, None, None)
+            .map_err(|inner| Error::WalletError {
+                wallet: wallet.as_ref().to_string(),
+                inner,
+            })?;
         Ok(Client::new(
             &self.rpc_url_with_wallet(wallet),
             Auth::CookieFile(self.params.cookie_file.clone()),
         )?)
     }
-}
 
-#[cfg(feature = "download")]
-impl BitcoinD {
-    /// create BitcoinD struct with the downloaded executable.
-    pub fn from_downloaded() -> anyhow::Result<BitcoinD> {
-        BitcoinD::new(downloaded_exe_path()?)
+    #[cfg(any(feature = "0_18_1", not(feature = "download")))]
+    /// Derive the addresses of the given `descriptor` (checksum included) calling `deriveaddresses`.
+    ///
+    /// `range` is the inclusive `(start, end)` derivation range and it's required for ranged
+    /// descriptors only
+    pub fn derive_addresses(
+        &self,
+        descriptor: &str,
+        range: Option<(u32, u32)>,
+    ) -> Result<Vec<bitcoin::Address<bitcoin::address::NetworkUnchecked>>> {
+        let range = range.map(|(start, end)| [start, end]);
+        Ok(self.client.derive_addresses(descriptor, range)?)
     }
-    /// create BitcoinD struct with the downloaded executable and given Conf.
-    pub fn from_downloaded_with_conf(conf: &Conf) -> anyhow::Result<BitcoinD> {
-        BitcoinD::with_conf(downloaded_exe_path()?, conf)
+
+    #[cfg(any(feature = "0_21_2", not(feature = "download")))]
+    /// Import all the given descriptors in the wallet connected to `wallet_client` with a single
+    /// `importdescriptors` call, returning whether each request succeeded in the same order
+    pub fn import_descriptors(
+        &self,
+        wallet_client: &Client,
+        requests: &[ImportDescriptorRequest],
+    ) -> Result<Vec<bool>> {
+        if requests.iter().any(|r| r.watchonly)
+            && wallet_client.get_wallet_info()?.private_keys_enabled
+        {
+            return Err(anyhow::anyhow!(
+                "watchonly descriptors require a wallet with private keys disabled"
+            )
+            .into());
+        }
+        let requests: Vec<Value> = requests
+            .iter()
+            .map(|r| {
+                let mut request = json!({
+                    "desc": r.descriptor,
+                    "timestamp": r.timestamp,
+                });
+                if let Some((start, end)) = r.range {
+                    request["range"] = json!([start, end]);
+                }
+                request
+            })
+            .collect();
+        let results: Vec<bitcoincore_rpc::json::ImportMultiResult> =
+            wallet_client.call("importdescriptors", &[Value::Array(requests)])?;
+        Ok(results.into_iter().map(|r| r.success).collect())
     }
-}
 
-impl Drop for BitcoinD {
-    fn drop(&mut self) {
-        if let DataDir::Persistent(_) = self.work_dir {
-            let _ = self.stop();
+    #[cfg(any(feature = "22_1", not(feature = "download")))]
+    /// Returns the active descriptors of the descriptor wallet connected to `wallet_client`, one
+    /// for each address type, split between receiving and change, calling `listdescriptors`.
+    ///
+    /// Private keys aren't included, the descriptors contain the extended public keys to derive
+    /// the addresses externally. Requires Bitcoin Core 22.0 or later
+    pub fn get_wallet_descriptor(&self, wallet_client: &Client) -> Result<WalletDescriptors> {
+        let result: Value = wallet_client.call("listdescriptors", &[])?;
+        let descriptors = result["descriptors"]
+            .as_array()
+            .context("listdescriptors without descriptors")?;
+        let mut external = vec![];
+        let mut internal = vec![];
+        for descriptor in descriptors {
+            if descriptor["active"].as_bool() != Some(true) {
+                continue;
+            }
+            let desc = descriptor["desc"]
+                .as_str()
+                .context("listdescriptors without desc")?
+                .to_string();
+            if descriptor["internal"].as_bool() == Some(true) {
+                internal.push(desc);
+            } else {
+                external.push(desc);
+            }
         }
-        let _ = self.process.kill();
+        Ok(WalletDescriptors { external, internal })
     }
-}
 
-/// Returns a non-used local port if available.
-///
-/// Note there is a race condition during the time the method check availability and the caller
-pub fn get_available_port() -> anyhow::Result<u16> {
-    // using 0 as port let the system assign a port available
-    let t = TcpListener::bind(("127.0.0.1", 0))?; // 0 means the OS choose a free port
-    Ok(t.local_addr().map(|s| s.port())?)
-}
+    /// Assign `label` to `address` in the wallet connected to `wallet_client` calling `setlabel`,
+    /// the address must belong to the wallet
+    pub fn set_label(
+        &self,
+        wallet_client: &Client,
+        address: &bitcoin::Address,
+        label: &str,
+    ) -> Result<()> {
+        wallet_client.call::<Value>("setlabel", &[address.to_string().into(), label.into()])?;
+        Ok(())
+    }
 
-impl From<std::io::Error> for Error {
-    fn from(e: std::io::Error) -> Self {
-        Error::Io(e)
+    /// Returns the addresses with `label` in the wallet connected to `wallet_client` calling
+    /// `getaddressesbylabel`, empty if the label is unknown
+    pub fn addresses_by_label(
+        &self,
+        wallet_client: &Client,
+        label: &str,
+    ) -> Result<Vec<bitcoin::Address>> {
+        let addresses: HashMap<String, Value> =
+            match wallet_client.call("getaddressesbylabel", &[label.into()]) {
+                Ok(addresses) => addresses,
+                // RPC_WALLET_INVALID_LABEL_NAME, returned when no address has the label
+                Err(bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(e)))
+                    if e.code == -11 =>
+                {
+                    return Ok(vec![])
+                }
+                Err(e) => return Err(e.into()),
+            };
+        let network = self
+            .params
+            .to_bitcoin_network()
+            .with_context(|| format!("unknown network {:?}", self.network))?;
+        addresses
+            .keys()
+            .map(|address| {
+                Ok(address
+                    .parse::<bitcoin::Address<_>>()
+                    .context("invalid getaddressesbylabel address")?
+                    .require_network(network)
+                    .context("getaddressesbylabel address of another network")?)
+            })
+            .collect()
     }
-}
 
-impl From<bitcoincore_rpc::Error> for Error {
-    fn from(e: bitcoincore_rpc::Error) -> Self {
-        Error::Rpc(e)
+    /// Import the private key `wif` in the wallet connected to `wallet_client` under `label`,
+    /// calling `importprivkey`. With `rescan` the call returns once the blockchain is rescanned
+    /// for transactions of the key, which may take long on big chains, meanwhile the progress is
+    /// logged like in [BitcoinD::rescan_blockchain].
+    ///
+    /// Legacy (non-descriptor) wallets only, the call fails on descriptor wallets which are the
+    /// default since 23.0: use [BitcoinD::import_descriptors] with a `wpkh(<wif>)` descriptor
+    /// instead.
+    pub fn import_private_key(
+        &self,
+        wallet_client: &Client,
+        wif: &str,
+        label: &str,
+        rescan: bool,
+    ) -> Result<()> {
+        let import = || {
+            wallet_client.call::<Value>("importprivkey", &[wif.into(), label.into(), rescan.into()])
+        };
+        if rescan {
+            self.with_rescan_progress(wallet_client, import)??;
+        } else {
+            import()?;
+        }
+        Ok(())
     }
-}
 
-/// Provide the bitcoind executable path if a version feature has been specified
-#[cfg(not(feature = "download"))]
-pub fn downloaded_exe_path() -> anyhow::Result<String> {
-    Err(Error::NoFeature.into())
-}
+    /// List the spendable outputs of the wallet connected to `wallet_client` having at least
+    /// `min_confirmations`
+    pub fn list_utxos(&self, wallet_client: &Client, min_confirmations: u32) -> Result<Vec<Utxo>> {
+        let unspent =
+            wallet_client.list_unspent(Some(min_confirmations as usize), None, None, None, None)?;
+        Ok(unspent
+            .into_iter()
+            .map(|u| Utxo {
+                txid: u.txid,
+                vout: u.vout,
+                amount: u.amount,
+                address: u.address,
+            })
+            .collect())
+    }
 
-/// Provide the bitcoind executable path if a version feature has been specified
-#[cfg(feature = "download")]
-pub fn downloaded_exe_path() -> anyhow::Result<String> {
-    if std::env::var_os("BITCOIND_SKIP_DOWNLOAD").is_some() {
-        return Err(Error::SkipDownload.into());
+    /// Returns the history of the wallet connected to `wallet_client` calling `listtransactions`,
+    /// the most recent `count` entries after skipping the `skip` most recent, oldest first.
+    ///
+    /// A transaction has one entry for each output sent or received, eg. a payment to an address
+    /// of the same wallet has both a [TxCategory::Send] and a [TxCategory::Receive] entry
+    pub fn list_transactions(
+        &self,
+        wallet_client: &Client,
+        count: usize,
+        skip: usize,
+    ) -> Result<Vec<WalletTransaction>> {
+        Ok(wallet_client
+            .list_transactions(None, Some(count), Some(skip), None)?
+            .into_iter()
+            .map(|t| WalletTransaction {
+                txid: t.info.txid,
+                category: t.detail.category.into(),
+                amount: t.detail.amount,
+                confirmations: t.info.confirmations as i64,
+            })
+            .collect())
     }
 
-    let mut path: PathBuf = env!("OUT_DIR").into();
-    path.push("bitcoin");
-    path.push(format!("bitcoin-{}", versions::VERSION));
-    path.push("bin");
+    /// Lock the given output so that the wallet doesn't spend it, see `lockunspent`
+    pub fn lock_utxo(&self, txid: &bitcoin::Txid, vout: u32) -> Result<()> {
+        let outpoint = bitcoin::OutPoint::new(*txid, vout);
+        if !self.client.lock_unspent(&[outpoint])? {
+            return Err(anyhow::anyhow!("cannot lock {}", outpoint).into());
+        }
+        Ok(())
+    }
 
-    if cfg!(target_os = "windows") {
-        path.push("bitcoind.exe");
-    } else {
-        path.push("bitcoind");
+    /// Unlock the given output previously locked with [BitcoinD::lock_utxo]
+    pub fn unlock_utxo(&self, txid: &bitcoin::Txid, vout: u32) -> Result<()> {
+        let outpoint = bitcoin::OutPoint::new(*txid, vout);
+        if !self.client.unlock_unspent(&[outpoint])? {
+            return Err(anyhow::anyhow!("cannot unlock {}", outpoint).into());
+        }
+        Ok(())
     }
 
-    Ok(format!("{}", path.display()))
-}
+    /// List the outputs currently locked in the wallet, see `listlockunspent`
+    pub fn list_locked_utxos(&self) -> Result<Vec<(bitcoin::Txid, u32)>> {
+        let locked: Vec<bitcoincore_rpc::JsonOutPoint> =
+            self.client.call("listlockunspent", &[])?;
+        Ok(locked.into_iter().map(|o| (o.txid, o.vout)).collect())
+    }
 
-/// Returns the daemon `bitcoind` executable with the following precedence:
-///
-/// 1) If it's specified in the `BITCOIND_EXE` env var
-/// 2) If there is no env var but an auto-download feature such as `23_1` is enabled, returns the
-/// path of the downloaded executabled
-/// 3) If neither of the precedent are available, the `bitcoind` executable is searched in the `PATH`
-pub fn exe_path() -> anyhow::Result<String> {
-    if let Ok(path) = std::env::var("BITCOIND_EXE") {
-        return Ok(path);
+    /// Send `amount` to `address` from the default wallet, returning the id of the transaction
+    pub fn send_to_address(
+        &self,
+        address: &bitcoin::Address,
+        amount: bitcoin::Amount,
+    ) -> Result<bitcoin::Txid> {
+        self.send_to_address_with_opts(address, amount, &SendToAddressOptions::default())
     }
-    if let Ok(path) = downloaded_exe_path() {
-        return Ok(path);
+
+    /// Like [BitcoinD::send_to_address] but allows to specify the optional parameters of
+    /// `sendtoaddress`
+    pub fn send_to_address_with_opts(
+        &self,
+        address: &bitcoin::Address,
+        amount: bitcoin::Amount,
+        opts: &SendToAddressOptions,
+    ) -> Result<bitcoin::Txid> {
+        Ok(self.client.send_to_address(
+            address,
+            amount,
+            opts.comment.as_deref(),
+            opts.comment_to.as_deref(),
+            opts.subtract_fee,
+            opts.replaceable,
+            opts.confirmation_target,
+            opts.estimate_mode,
+        )?)
     }
-    which::which("bitcoind")
-        .map_err(|_| Error::NoBitcoindExecutableFound.into())
-        .map(|p| p.display().to_string())
-}
 
-/// Validate the specified arg if there is any unavailable or deprecated one
-pub fn validate_args(args: Vec<&str>) -> anyhow::Result<Vec<&str>> {
-    args.iter().try_for_each(|arg| {
-        // other kind of invalid arguments can be added into the list if needed
-        if INVALID_ARGS.iter().any(|x| arg.starts_with(x)) {
-            return Err(Error::RpcUserAndPasswordUsed);
+    #[cfg(any(feature = "0_21_2", not(feature = "download")))]
+    /// Replace the wallet transaction `txid`, which must signal BIP125 replace-by-fee, with one
+    /// paying `new_fee_rate` in sat/vByte calling `bumpfee`, returns the id of the replacement.
+    ///
+    /// Requires bitcoind 0.21 or newer since before the `fee_rate` option was in BTC/kvB
+    pub fn bump_fee(
+        &self,
+        wallet_client: &Client,
+        txid: &bitcoin::Txid,
+        new_fee_rate: f64,
+    ) -> Result<bitcoin::Txid> {
+        let result: Value = wallet_client.call(
+            "bumpfee",
+            &[txid.to_string().into(), json!({ "fee_rate": new_fee_rate })],
+        )?;
+        Ok(result["txid"]
+            .as_str()
+            .context("bumpfee without txid")?
+            .parse()
+            .context("invalid bumpfee txid")?)
+    }
+
+    /// Send all the funds of the default wallet to `destination`, returning `None` if there is
+    /// nothing to send.
+    ///
+    /// It uses `sendall` on bitcoind 24.0 and newer, while on older versions the whole trusted
+    /// balance is sent with the fee subtracted from the amount
+    pub fn sweep_wallet(&self, destination: &bitcoin::Address) -> Result<Option<bitcoin::Txid>> {
+        let balance = self.client.get_balance(None, None)?;
+        if balance == bitcoin::Amount::ZERO {
+            return Ok(None);
+        }
+        if self.client.version()? >= 240_000 {
+            let result: Value = self
+                .client
+                .call("sendall", &[json!([destination.to_string()])])?;
+            let txid = result["txid"]
+                .as_str()
+                .with_context(|| format!("sendall didn't return a txid: {}", result))?;
+            Ok(Some(txid.parse().context("invalid sendall txid")?))
+        } else {
+            let opts = SendToAddressOptions {
+                subtract_fee: Some(true),
+                ..Default::default()
+            };
+            Ok(Some(self.send_to_address_with_opts(
+                destination,
+                balance,
+                &opts,
+            )?))
+        }
+    }
+
+    /// Combine up to `max_inputs` of the lowest value confirmed UTXOs of the wallet connected to
+    /// `wallet_client` in a single output to a new address of the same wallet, returning the
+    /// broadcast transaction or `None` if there are less than 2 UTXOs to combine.
+    ///
+    /// Keeps the wallet UTXO set small, eg. after mining many blocks to the wallet, since big sets
+    /// slow down wallet RPC calls. The fee is subtracted from the consolidated output
+    pub fn consolidate_utxos(
+        &self,
+        wallet_client: &Client,
+        max_inputs: usize,
+    ) -> Result<Option<bitcoin::Txid>> {
+        let mut utxos = self.list_utxos(wallet_client, 1)?;
+        if max_inputs < 2 || utxos.len() < 2 {
+            return Ok(None);
+        }
+        utxos.sort_by_key(|u| u.amount);
+        utxos.truncate(max_inputs);
+
+        let inputs: Vec<_> = utxos
+            .iter()
+            .map(|u| bitcoincore_rpc::json::CreateRawTransactionInput {
+                txid: u.txid,
+                vout: u.vout,
+                sequence: None,
+            })
+            .collect();
+        let total = utxos.iter().map(|u| u.amount).sum();
+        let address = wallet_client.get_new_address(None, None)?.assume_checked();
+        let mut outputs = HashMap::new();
+        outputs.insert(address.to_string(), total);
+        let raw = wallet_client.create_raw_transaction_hex(&inputs, &outputs, None, None)?;
+
+        let options = bitcoincore_rpc::json::FundRawTransactionOptions {
+            subtract_fee_from_outputs: Some(vec![0]),
+            ..Default::default()
+        };
+        let funded = wallet_client.fund_raw_transaction(raw, Some(&options), None)?;
+        let signed = wallet_client.sign_raw_transaction_with_wallet(&funded.hex, None, None)?;
+        if !signed.complete {
+            return Err(anyhow::anyhow!("consolidation transaction not fully signed").into());
+        }
+        Ok(Some(wallet_client.send_raw_transaction(&signed.hex)?))
+    }
+
+    /// Rescan the blockchain from `start_height` (genesis if `None`) for the wallet connected to
+    /// `wallet_client`, returning the height where the rescan stopped.
+    ///
+    /// The call blocks until the rescan is finished, meanwhile the progress is logged at debug
+    /// level every 10 seconds (only on bitcoind 0.21 and newer)
+    pub fn rescan_blockchain(
+        &self,
+        wallet_client: &Client,
+        start_height: Option<u64>,
+    ) -> Result<u64> {
+        let (_, stop_height) = self.with_rescan_progress(wallet_client, || {
+            wallet_client.rescan_blockchain(start_height.map(|h| h as usize), None)
+        })??;
+        let stop_height = stop_height.context("rescan was aborted")?;
+        Ok(stop_height as u64)
+    }
+
+    /// Call `rescan`, a blocking call rescanning the blockchain for the wallet connected to
+    /// `wallet_client`, logging the rescan progress at debug level every 10 seconds (only on
+    /// bitcoind 0.21 and newer)
+    fn with_rescan_progress<T>(
+        &self,
+        wallet_client: &Client,
+        rescan: impl FnOnce() -> T,
+    ) -> Result<T> {
+        let wallet_name = wallet_client.get_wallet_info()?.wallet_name;
+        let wallet_url = format!("http://{}/wallet/{}", self.params.rpc_socket, wallet_name);
+        let cookie_file = self.params.cookie_file.clone();
+        let (done_sender, done_receiver) = mpsc::channel::<()>();
+        let progress = thread::spawn(move || -> anyhow::Result<()> {
+            let client = Client::new(&wallet_url, Auth::CookieFile(cookie_file))?;
+            while done_receiver.recv_timeout(Duration::from_secs(10)).is_err() {
+                if let Some(ScanningDetails::Scanning { duration, progress }) =
+                    client.get_wallet_info()?.scanning
+                {
+                    debug!(
+                        "rescan of wallet {:?} {:.1}% done in {}s",
+                        wallet_name,
+                        progress * 100.0,
+                        duration
+                    );
+                }
+            }
+            Ok(())
+        });
+
+        let result = rescan();
+        let _ = done_sender.send(());
+        let _ = progress.join();
+        Ok(result)
+    }
+
+    /// Get the transaction with the given `txid` calling `getrawtransaction`.
+    ///
+    /// It's not a wallet rpc so any transaction in the mempool is returned, while confirmed
+    /// transactions not in the mempool require the node to run with `-txindex`
+    pub fn get_tx(&self, txid: &bitcoin::Txid) -> Result<bitcoin::Transaction> {
+        Ok(self.client.get_raw_transaction(txid, None)?)
+    }
+
+    /// Broadcast the consensus serialized `raw_tx` and mine a block to an address of the default
+    /// wallet, returns the txid and the hash of the block.
+    ///
+    /// An error is returned if the transaction isn't in the mined block, eg. because its fee
+    /// rate is below `-blockmintxfee`
+    pub fn broadcast_and_mine(&self, raw_tx: &[u8]) -> Result<(bitcoin::Txid, bitcoin::BlockHash)> {
+        let txid = self.client.send_raw_transaction(raw_tx)?;
+        let address = self.get_receive_address()?;
+        let block_hash = self
+            .client
+            .generate_to_address(1, &address)?
+            .pop()
+            .context("no block mined")?;
+        let block = self.client.get_block(&block_hash)?;
+        if !block.txdata.iter().any(|tx| tx.compute_txid() == txid) {
+            return Err(
+                anyhow::anyhow!("transaction {} not mined in block {}", txid, block_hash).into(),
+            );
+        }
+        Ok((txid, block_hash))
+    }
+
+    /// Mine a block on top of the tip containing only the consensus serialized `raw_tx` besides
+    /// the coinbase, bypassing the mempool: the transaction is confirmed even if its fee rate or
+    /// its standardness would exclude it from the mempool, its inputs must be confirmed though.
+    ///
+    /// The block is built from `getblocktemplate`, with the subsidy paid to an address of the
+    /// default wallet, and sent with `submitblock`. Returns the hash of the block, checking it's
+    /// the new tip
+    pub fn mine_block_with_transaction(&self, raw_tx: &[u8]) -> Result<bitcoin::BlockHash> {
+        use bitcoin::blockdata::{block, opcodes, script};
+        use bitcoin::hashes::Hash;
+        use bitcoin::{consensus, Amount, Block, CompactTarget, Transaction, TxIn, TxOut, Witness};
+
+        let tx: Transaction = consensus::deserialize(raw_tx).context("invalid transaction")?;
+        let template: Value = self
+            .client
+            .call("getblocktemplate", &[json!({ "rules": ["segwit"] })])?;
+        let height = template["height"]
+            .as_i64()
+            .context("getblocktemplate without height")?;
+        let fees: u64 = template["transactions"]
+            .as_array()
+            .context("getblocktemplate without transactions")?
+            .iter()
+            .filter_map(|tx| tx["fee"].as_u64())
+            .sum();
+        let subsidy = template["coinbasevalue"]
+            .as_u64()
+            .context("getblocktemplate without coinbasevalue")?
+            - fees;
+        let bits = template["bits"]
+            .as_str()
+            .and_then(|bits| u32::from_str_radix(bits, 16).ok())
+            .context("getblocktemplate without bits")?;
+
+        // BIP34 height, the extra push satisfies the minimum script length at low heights
+        let script_sig = script::Builder::new()
+            .push_int(height)
+            .push_opcode(opcodes::OP_0)
+            .into_script();
+        let coinbase = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                script_sig,
+                witness: Witness::from_slice(&[[0u8; 32]]),
+                ..TxIn::default()
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(subsidy),
+                script_pubkey: self.get_receive_address()?.script_pubkey(),
+            }],
+        };
+        let mut block = Block {
+            header: block::Header {
+                version: block::Version::from_consensus(
+                    template["version"]
+                        .as_i64()
+                        .context("getblocktemplate without version")? as i32,
+                ),
+                prev_blockhash: template["previousblockhash"]
+                    .as_str()
+                    .context("getblocktemplate without previousblockhash")?
+                    .parse()
+                    .context("invalid previousblockhash")?,
+                merkle_root: bitcoin::TxMerkleNode::all_zeros(),
+                time: template["curtime"]
+                    .as_u64()
+                    .context("getblocktemplate without curtime")? as u32,
+                bits: CompactTarget::from_consensus(bits),
+                nonce: 0,
+            },
+            txdata: vec![coinbase, tx],
+        };
+
+        // the witness root doesn't depend on the coinbase, so it can be committed in it
+        let witness_root = block.witness_root().context("empty block")?;
+        let commitment = Block::compute_witness_commitment(&witness_root, &[0u8; 32]);
+        let mut commitment_bytes = [0u8; 36];
+        commitment_bytes[..4].copy_from_slice(&[0xaa, 0x21, 0xa9, 0xed]);
+        commitment_bytes[4..].copy_from_slice(commitment.as_byte_array());
+        block.txdata[0].output.push(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: script::Builder::new()
+                .push_opcode(opcodes::all::OP_RETURN)
+                .push_slice(commitment_bytes)
+                .into_script(),
+        });
+        block.header.merkle_root = block.compute_merkle_root().context("empty block")?;
+        while block.header.validate_pow(block.header.target()).is_err() {
+            block.header.nonce += 1;
+        }
+
+        self.client.submit_block(&block)?;
+        let block_hash = block.block_hash();
+        if self.client.get_best_block_hash()? != block_hash {
+            return Err(anyhow::anyhow!("block {} not accepted as the tip", block_hash).into());
+        }
+        Ok(block_hash)
+    }
+
+    /// Returns the number of confirmations of the transaction with the given `txid`, `None` if
+    /// it's in the mempool.
+    ///
+    /// Like [BitcoinD::get_tx] it requires `-txindex` for confirmed transactions, an error is
+    /// returned if the transaction is unknown to the node
+    pub fn get_tx_confirmations(&self, txid: &bitcoin::Txid) -> Result<Option<u32>> {
+        match self.tx_confirmations(txid) {
+            Ok(confirmations) => Ok(confirmations),
+            Err(e) if is_rpc_error(&e, RPC_INVALID_ADDRESS_OR_KEY) => {
+                Err(e).with_context(|| format!("transaction {} unknown to the node", txid))?
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// [BitcoinD::get_tx_confirmations] returning the RPC error as is
+    fn tx_confirmations(
+        &self,
+        txid: &bitcoin::Txid,
+    ) -> std::result::Result<Option<u32>, bitcoincore_rpc::Error> {
+        let info = self.client.get_raw_transaction_info(txid, None)?;
+        Ok(info.confirmations.filter(|c| *c > 0))
+    }
+
+    /// Whether the transaction index is enabled with `-txindex`, needed by `getrawtransaction` to
+    /// return transactions not in the mempool nor the wallet when the block isn't specified.
+    ///
+    /// Calls `getindexinfo` on 0.21 and later, on older nodes infers it from the error returned by
+    /// `getrawtransaction` for an unknown transaction, which suggests `-txindex` if not enabled
+    pub fn is_txindex_enabled(&self) -> Result<bool> {
+        match self.client.call::<Value>("getindexinfo", &[]) {
+            Ok(info) => return Ok(info.get("txindex").is_some()),
+            Err(e) if !is_method_not_found(&e) => return Err(e.into()),
+            Err(_) => (),
+        }
+        use bitcoin::hashes::Hash;
+        match self
+            .client
+            .get_raw_transaction_hex(&bitcoin::Txid::all_zeros(), None)
+        {
+            Err(bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(e))) => {
+                Ok(!e.message.contains("-txindex"))
+            }
+            Err(e) => Err(e.into()),
+            Ok(_) => Err(anyhow::anyhow!("unexpected transaction with zero txid").into()),
+        }
+    }
+
+    /// Wait until the transaction `txid` has at least `confirmations` confirmations, polling
+    /// [BitcoinD::get_tx_confirmations] every 100ms, so `-txindex` is required as well.
+    ///
+    /// A transaction unknown to the node counts as not confirmed, like a confirmed one without
+    /// `-txindex`, other errors are returned immediately. An error with the current confirmations
+    /// is returned if they aren't reached within `timeout`
+    #[must_use = "the wait result should be checked, an error means the condition wasn't met in time"]
+    pub fn wait_for_tx_confirmation(
+        &self,
+        txid: &bitcoin::Txid,
+        confirmations: u32,
+        timeout: Duration,
+    ) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let current = match self.tx_confirmations(txid) {
+                Ok(confirmations) => confirmations.unwrap_or(0),
+                Err(e) if is_rpc_error(&e, RPC_INVALID_ADDRESS_OR_KEY) => 0,
+                Err(e) => return Err(e.into()),
+            };
+            if current >= confirmations {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!(
+                    "transaction {} has {} confirmations after {:?}, {} expected",
+                    txid,
+                    current,
+                    timeout,
+                    confirmations
+                )
+                .into());
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Returns all the chain tips known by the node, including the active one, see `getchaintips`
+    pub fn get_chain_tips(&self) -> Result<Vec<ChainTip>> {
+        Ok(self
+            .client
+            .get_chain_tips()?
+            .into_iter()
+            .map(|t| ChainTip {
+                height: t.height,
+                hash: t.hash,
+                branch_length: t.branch_length as u32,
+                status: t.status.into(),
+            })
+            .collect())
+    }
+
+    /// Reorg the chain of the connected `victim` node: the network of both nodes is deactivated,
+    /// the victim mines `orphan_depth` blocks which will be orphaned while this node mines
+    /// `orphan_depth + new_chain_lead` blocks, then the network is reactivated and the victim is
+    /// waited to switch to this node chain, up to 30 seconds.
+    ///
+    /// This node must have the p2p port open, eg. with [P2P::Yes], so that the victim can connect
+    /// to it again after the network is reactivated
+    pub fn simulate_reorg(
+        &self,
+        victim: &BitcoinD,
+        orphan_depth: u64,
+        new_chain_lead: u64,
+    ) -> Result<()> {
+        let p2p_socket = self
+            .params
+            .p2p_socket
+            .context("the attacker node must have the p2p port open")?;
+
+        self.disconnect_network()?;
+        victim.disconnect_network()?;
+
+        let victim_address = victim.get_receive_address()?;
+        victim
+            .client
+            .generate_to_address(orphan_depth, &victim_address)?;
+        let address = self.get_receive_address()?;
+        self.client
+            .generate_to_address(orphan_depth + new_chain_lead, &address)?;
+        let tip = self.client.get_best_block_hash()?;
+
+        self.reconnect_network()?;
+        victim.reconnect_network()?;
+        // nodes connected with `-connect` reconnect on their own, the others need a new connection
+        let _ = victim.client.onetry_node(&p2p_socket.to_string());
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while victim.client.get_best_block_hash()? != tip {
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!("victim didn't reorg to {} in 30 seconds", tip).into());
+            }
+            thread::sleep(Duration::from_millis(100));
         }
         Ok(())
-    })?;
+    }
+
+    /// Returns the fee rate estimated for confirmation within `target_blocks` calling
+    /// `estimatesmartfee`.
+    ///
+    /// Without enough fee history, as in a fresh regtest node, the `-fallbackfee` in [Conf::args]
+    /// is returned, or the node minimum relay fee if it's missing
+    pub fn get_fee_rate(&self, target_blocks: u16) -> Result<bitcoin::FeeRate> {
+        let estimate = self.client.estimate_smart_fee(target_blocks, None)?;
+        if let Some(rate) = estimate.fee_rate {
+            return Ok(fee_rate_from_btc_per_kvb(rate.to_btc()));
+        }
+        match self.fallback_fee {
+            Some(rate) => Ok(rate),
+            None => Ok(fee_rate_from_btc_per_kvb(self.network_info()?.relay_fee)),
+        }
+    }
+
+    /// Disable all p2p network activity calling `setnetworkactive false`, simulating the node going
+    /// offline: peers are disconnected and no new connection is made until
+    /// [BitcoinD::reconnect_network]
+    pub fn disconnect_network(&self) -> Result<()> {
+        self.client.set_network_active(false)?;
+        Ok(())
+    }
+
+    /// Enable again p2p network activity disabled with [BitcoinD::disconnect_network], peers are
+    /// not reconnected immediately, use [BitcoinD::add_node] to speed it up
+    pub fn reconnect_network(&self) -> Result<()> {
+        self.client.set_network_active(true)?;
+        Ok(())
+    }
+
+    /// Whether p2p network activity is enabled, as reported by `getnetworkinfo`
+    pub fn network_active(&self) -> Result<bool> {
+        Ok(self.client.get_network_info()?.network_active)
+    }
+
+    /// Ban `addr`, an IP or a subnet in CIDR notation, for `duration_secs` (bitcoind default of
+    /// 24 hours if `None`). Connected peers matching the ban are disconnected
+    pub fn ban_peer(&self, addr: &str, duration_secs: Option<u64>) -> Result<()> {
+        Ok(self
+            .client
+            .add_ban(addr, duration_secs.unwrap_or(0), false)?)
+    }
+
+    /// List the banned subnets in CIDR notation, eg. `127.0.0.1/32`, see `listbanned`
+    pub fn list_banned(&self) -> Result<Vec<String>> {
+        Ok(self
+            .client
+            .list_banned()?
+            .into_iter()
+            .map(|b| b.address)
+            .collect())
+    }
+
+    /// Disconnect the given peer without banning it, see `disconnectnode`
+    pub fn disconnect_peer(&self, peer: &PeerRef) -> Result<()> {
+        match peer {
+            PeerRef::Address(addr) => self.client.disconnect_node(&addr.to_string())?,
+            PeerRef::Id(id) => self
+                .client
+                .call("disconnectnode", &["".into(), (*id).into()])?,
+        }
+        Ok(())
+    }
+
+    /// Add `addr` to the peers the node keeps connecting to, like `-addnode` does at launch
+    pub fn add_node(&self, addr: &SocketAddrV4) -> Result<()> {
+        Ok(self.client.add_node(&addr.to_string())?)
+    }
+
+    /// Remove `addr` from the peers added with [BitcoinD::add_node], an existing connection is
+    /// not closed, use [BitcoinD::disconnect_peer] for that
+    pub fn remove_node(&self, addr: &SocketAddrV4) -> Result<()> {
+        Ok(self.client.remove_node(&addr.to_string())?)
+    }
+
+    /// List the peers added with [BitcoinD::add_node] or `-addnode`
+    pub fn list_added_nodes(&self) -> Result<Vec<SocketAddrV4>> {
+        self.client
+            .get_added_node_info(None)?
+            .into_iter()
+            .map(|n| {
+                n.added_node
+                    .parse()
+                    .with_context(|| format!("cannot parse added node {}", n.added_node))
+                    .map_err(Error::from)
+            })
+            .collect()
+    }
+
+    /// Validate the given `address` string calling `validateaddress`.
+    ///
+    /// Since bitcoind 0.18 `validateaddress` doesn't return wallet information, so for valid
+    /// addresses `is_mine` and `is_watch_only` are taken from `getaddressinfo` of the default
+    /// wallet. They are `None` if the node has no default wallet, eg. with [Conf::no_wallet]
+    pub fn validate_address(&self, address: &str) -> Result<AddressInfo> {
+        let validated: Value = self.client.call("validateaddress", &[address.into()])?;
+        let is_valid = validated["isvalid"].as_bool().unwrap_or(false);
+        let mut info = AddressInfo {
+            is_valid,
+            address: validated["address"].as_str().map(ToString::to_string),
+            script_pub_key: validated["scriptPubKey"].as_str().map(ToString::to_string),
+            is_mine: validated["ismine"].as_bool(),
+            is_watch_only: validated["iswatchonly"].as_bool(),
+        };
+        if is_valid && info.is_mine.is_none() && !self.no_wallet {
+            match self
+                .client
+                .call::<Value>("getaddressinfo", &[address.into()])
+            {
+                Ok(wallet_info) => {
+                    info.is_mine = wallet_info["ismine"].as_bool();
+                    info.is_watch_only = wallet_info["iswatchonly"].as_bool();
+                }
+                Err(e)
+                    if is_rpc_error(&e, RPC_WALLET_NOT_FOUND)
+                        || is_rpc_error(&e, RPC_WALLET_NOT_SPECIFIED) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(info)
+    }
+
+    /// Decode the consensus serialized `raw_tx` calling `decoderawtransaction`, useful to see the
+    /// bitcoind view of a transaction while debugging
+    pub fn decode_raw_transaction(&self, raw_tx: &[u8]) -> Result<Value> {
+        Ok(self.client.call(
+            "decoderawtransaction",
+            &[raw_tx.to_lower_hex_string().into()],
+        )?)
+    }
+
+    /// Decode `script` calling `decodescript`.
+    ///
+    /// Recent bitcoind versions return a single `address` while older ones return a list of
+    /// `addresses`, both are collected in [ScriptInfo::addresses]
+    pub fn decode_script(&self, script: &bitcoin::Script) -> Result<ScriptInfo> {
+        let decoded: Value = self
+            .client
+            .call("decodescript", &[script.to_hex_string().into()])?;
+        let addresses = match (decoded["address"].as_str(), decoded["addresses"].as_array()) {
+            (Some(address), _) => vec![address.to_string()],
+            (None, Some(addresses)) => addresses
+                .iter()
+                .filter_map(|a| a.as_str().map(ToString::to_string))
+                .collect(),
+            (None, None) => vec![],
+        };
+        Ok(ScriptInfo {
+            asm: decoded["asm"]
+                .as_str()
+                .context("decodescript without asm")?
+                .to_string(),
+            type_: decoded["type"]
+                .as_str()
+                .context("decodescript without type")?
+                .to_string(),
+            addresses,
+        })
+    }
+
+    /// Create an `n` of `keys.len()` multisig address calling `createmultisig`, the keys are
+    /// passed in compressed form.
+    ///
+    /// The address isn't imported in any wallet, it's a legacy P2SH address, the default
+    /// `address_type` of `createmultisig`, which isn't affected by `-addresstype`
+    pub fn create_multisig(&self, n: u8, keys: &[bitcoin::PublicKey]) -> Result<MultisigResult> {
+        let keys: Vec<String> = keys
+            .iter()
+            .map(|k| k.inner.serialize().to_lower_hex_string())
+            .collect();
+        let created: Value = self
+            .client
+            .call("createmultisig", &[n.into(), keys.into()])?;
+        let network = self
+            .params
+            .to_bitcoin_network()
+            .with_context(|| format!("unknown network {:?}", self.network))?;
+        let address = created["address"]
+            .as_str()
+            .context("createmultisig without address")?
+            .parse::<bitcoin::Address<_>>()
+            .context("invalid multisig address")?
+            .require_network(network)
+            .context("multisig address of another network")?;
+        let redeem_script = bitcoin::ScriptBuf::from_hex(
+            created["redeemScript"]
+                .as_str()
+                .context("createmultisig without redeemScript")?,
+        )
+        .context("invalid redeem script")?;
+        Ok(MultisigResult {
+            address,
+            redeem_script,
+        })
+    }
+
+    /// Create a PSBT spending `inputs`, given as `(txid, vout)`, to `outputs` calling
+    /// `createpsbt`, which doesn't need a wallet. The PSBT contains no UTXO information nor
+    /// signatures, see [BitcoinD::sign_psbt]
+    pub fn create_psbt(
+        &self,
+        inputs: &[(bitcoin::Txid, u32)],
+        outputs: &HashMap<bitcoin::Address, bitcoin::Amount>,
+    ) -> Result<Psbt> {
+        let inputs: Vec<Value> = inputs
+            .iter()
+            .map(|(txid, vout)| json!({ "txid": txid.to_string(), "vout": vout }))
+            .collect();
+        let outputs: serde_json::Map<String, Value> = outputs
+            .iter()
+            .map(|(address, amount)| (address.to_string(), json!(amount.to_btc())))
+            .collect();
+        let psbt: String = self
+            .client
+            .call("createpsbt", &[inputs.into(), outputs.into()])?;
+        let bytes = base64::decode(&psbt).context("invalid base64 psbt")?;
+        Ok(Psbt::deserialize(&bytes).context("invalid psbt")?)
+    }
+
+    /// Sign the inputs of `psbt` owned by the wallet of `wallet_client` calling
+    /// `walletprocesspsbt`, returns the updated PSBT and whether it's complete, meaning all its
+    /// inputs are signed and it can be finalized.
+    ///
+    /// The PSBT is exchanged with the node in standard base64 encoding
+    pub fn sign_psbt(&self, wallet_client: &Client, psbt: &Psbt) -> Result<(Psbt, bool)> {
+        let result = wallet_client.wallet_process_psbt(
+            &base64::encode(psbt.serialize()),
+            None,
+            None,
+            None,
+        )?;
+        let bytes = base64::decode(&result.psbt).context("invalid base64 psbt")?;
+        let psbt = Psbt::deserialize(&bytes).context("invalid psbt")?;
+        Ok((psbt, result.complete))
+    }
+
+    /// Finalize the inputs of `psbt` and extract the transaction calling `finalizepsbt`, returns
+    /// `None` if the PSBT isn't complete yet, eg. because it lacks some signatures
+    pub fn finalize_psbt(&self, psbt: &Psbt) -> Result<Option<bitcoin::Transaction>> {
+        let result = self
+            .client
+            .finalize_psbt(&base64::encode(psbt.serialize()), None)?;
+        match result.hex {
+            Some(hex) if result.complete => Ok(Some(
+                bitcoin::consensus::deserialize(&hex).context("invalid finalized transaction")?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the transactions in the mempool calling `getrawmempool`, the txids or, if `query` is
+    /// [MempoolQuery::Entries], their details in the matching [RawMempool] variant
+    pub fn raw_mempool(&self, query: MempoolQuery) -> Result<RawMempool> {
+        match query {
+            MempoolQuery::Txids => Ok(RawMempool::Txids(self.client.get_raw_mempool()?)),
+            MempoolQuery::Entries => Ok(RawMempool::Entries(
+                self.client
+                    .get_raw_mempool_verbose()?
+                    .into_iter()
+                    .map(|(txid, e)| {
+                        let entry = MempoolEntry {
+                            fee: e.fees.base,
+                            vsize: e.vsize,
+                            weight: e.weight.unwrap_or(e.vsize * 4),
+                            depends: e.depends,
+                            fee_rate: e.fees.base.to_sat() as f64 / e.vsize as f64,
+                        };
+                        (txid, entry)
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Returns the number of in-mempool ancestors of the mempool transaction `txid`, itself
+    /// included, calling `getmempoolentry`
+    pub fn ancestor_count(&self, txid: &bitcoin::Txid) -> Result<u32> {
+        Ok(self.client.get_mempool_entry(txid)?.ancestor_count as u32)
+    }
+
+    /// Returns the number of in-mempool descendants of the mempool transaction `txid`, itself
+    /// included, calling `getmempoolentry`
+    pub fn descendant_count(&self, txid: &bitcoin::Txid) -> Result<u32> {
+        Ok(self.client.get_mempool_entry(txid)?.descendant_count as u32)
+    }
+
+    #[cfg(any(feature = "0_21_2", not(feature = "download")))]
+    /// Returns the number of mempool transactions submitted locally and not yet received by any
+    /// peer, from the `unbroadcastcount` of `getmempoolinfo`, to verify the rebroadcast logic.
+    ///
+    /// Requires Bitcoin Core 0.21 or later
+    pub fn unbroadcast_count(&self) -> Result<u32> {
+        let info: Value = self.client.call("getmempoolinfo", &[])?;
+        let count = info["unbroadcastcount"]
+            .as_u64()
+            .context("getmempoolinfo without unbroadcastcount")?;
+        Ok(count as u32)
+    }
+
+    #[cfg(any(feature = "24_0_1", not(feature = "download")))]
+    /// Returns the mempool transaction spending `outpoint`, if any, calling
+    /// `gettxspendingprevout`. Spends in blocks aren't considered.
+    ///
+    /// Requires Bitcoin Core 24.0 or later
+    pub fn get_tx_spending_prevout(
+        &self,
+        outpoint: &bitcoin::OutPoint,
+    ) -> Result<Option<bitcoin::Txid>> {
+        let outputs = json!([{ "txid": outpoint.txid.to_string(), "vout": outpoint.vout }]);
+        let result: Value = self.client.call("gettxspendingprevout", &[outputs])?;
+        match result[0]["spendingtxid"].as_str() {
+            Some(txid) => Ok(Some(
+                txid.parse().context("invalid gettxspendingprevout txid")?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns statistics about the UTXO set calling `gettxoutsetinfo`, equal values mean equal
+    /// chain states.
+    ///
+    /// The whole UTXO set is scanned, which is slow on mainnet but fine in regtest with few
+    /// blocks. It doesn't work with `-coinstatsindex` since the number of transactions is missing
+    pub fn txoutsetinfo(&self) -> Result<TxOutSetInfo> {
+        let info = self.client.get_tx_out_set_info(None, None, None)?;
+        Ok(TxOutSetInfo {
+            height: info.height,
+            best_block: info.best_block,
+            transactions: info
+                .transactions
+                .context("gettxoutsetinfo without transactions, is -coinstatsindex enabled?")?,
+            txouts: info.tx_outs,
+            total_amount: info.total_amount,
+        })
+    }
+
+    /// Verify the blockchain database calling `verifychain`, `check_level` goes from 0 to 4 with
+    /// the same meaning of `-checklevel` and `num_blocks` equal to 0 means all the blocks
+    pub fn verify_chain(&self, check_level: u8, num_blocks: u64) -> Result<bool> {
+        if check_level > 4 {
+            return Err(anyhow::anyhow!(
+                "check_level must be between 0 and 4, got {}",
+                check_level
+            )
+            .into());
+        }
+        Ok(self
+            .client
+            .call("verifychain", &[check_level.into(), num_blocks.into()])?)
+    }
+
+    #[cfg(any(feature = "26_0", not(feature = "download")))]
+    /// Load the UTXO set snapshot at `path`, created with `dumptxoutset`, calling `loadtxoutset`
+    /// for AssumeUTXO tests, then wait up to `timeout` for the background validation of the
+    /// snapshot to complete, polling `getchainstates`.
+    ///
+    /// The node must have the headers up to the snapshot base block, which on regtest must match
+    /// the hardcoded assumeutxo data. Requires Bitcoin Core 26.0 or later.
+    #[must_use = "the wait result should be checked, an error means the condition wasn't met in time"]
+    pub fn load_txout_set(&self, path: &Path, timeout: Duration) -> Result<()> {
+        let path = path
+            .to_str()
+            .context("snapshot path is not valid unicode")?;
+        self.client.call::<Value>("loadtxoutset", &[path.into()])?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let states: Value = self.client.call("getchainstates", &[])?;
+            let validated = states["chainstates"].as_array().map_or(false, |states| {
+                states
+                    .iter()
+                    .all(|state| state["validated"].as_bool() == Some(true))
+            });
+            if validated {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                return Err(
+                    anyhow::anyhow!("snapshot {} not validated in {:?}", path, timeout).into(),
+                );
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Returns the node version, connections and relay fee calling `getnetworkinfo`
+    pub fn network_info(&self) -> Result<NetworkInfo> {
+        Ok(self.client.call("getnetworkinfo", &[])?)
+    }
+
+    /// Returns the balances and the rescan progress of the default wallet calling
+    /// `getwalletinfo`
+    pub fn wallet_info(&self) -> Result<WalletInfo> {
+        Ok(self.client.call("getwalletinfo", &[])?)
+    }
+
+    /// Returns the balances of the default wallet by category calling `getbalances`, available
+    /// since 0.19, while on older nodes the balances of `getwalletinfo` are used, without
+    /// [BalanceDetails::used]
+    pub fn get_balance_details(&self) -> Result<BalanceDetails> {
+        match self.client.call::<Value>("getbalances", &[]) {
+            Ok(balances) => Ok(serde_json::from_value(balances["mine"].clone())
+                .context("invalid getbalances result")?),
+            Err(e) if !is_method_not_found(&e) => Err(e.into()),
+            Err(_) => {
+                let info = self.wallet_info()?;
+                Ok(BalanceDetails {
+                    trusted: info.balance,
+                    untrusted_pending: info.unconfirmed_balance,
+                    immature: info.immature_balance,
+                    used: None,
+                })
+            }
+        }
+    }
+
+    /// Returns the block height and the difficulty calling `getmininginfo`
+    pub fn mining_info(&self) -> Result<MiningInfo> {
+        Ok(self.client.call("getmininginfo", &[])?)
+    }
+
+    #[cfg(any(feature = "0_18_1", not(feature = "download")))]
+    /// Returns the RPC calls in progress calling `getrpcinfo`, eg. to check a long running call
+    /// like `rescanblockchain` isn't in progress before issuing a conflicting one.
+    ///
+    /// Requires Bitcoin Core 0.18 or later, older nodes return a method not found error
+    pub fn rpc_info(&self) -> Result<RpcInfo> {
+        let info: Value = self.client.call("getrpcinfo", &[])?;
+        let active_calls = info["active_commands"]
+            .as_array()
+            .context("getrpcinfo without active_commands")?
+            .iter()
+            .filter_map(|command| command["method"].as_str())
+            .filter(|method| *method != "getrpcinfo")
+            .map(ToString::to_string)
+            .collect();
+        Ok(RpcInfo { active_calls })
+    }
+
+    #[cfg(any(feature = "0_18_1", not(feature = "download")))]
+    /// Returns the fee rates, transaction count and sizes of the block at `height` calling
+    /// `getblockstats`, fee rates are 0 for blocks with only the coinbase
+    pub fn get_block_stats(&self, height: u64) -> Result<BlockStats> {
+        Ok(self.client.call("getblockstats", &[height.into()])?)
+    }
+
+    /// Returns the disk usage in bytes of the node working directory, summing the size of the
+    /// files recursively
+    pub fn work_dir_size(&self) -> Result<u64> {
+        Ok(dir_size(&self.work_dir.path())?)
+    }
+
+    /// Returns the last `lines` lines of the debug log, useful in test failure messages.
+    ///
+    /// The log is read backward from the end so it isn't loaded entirely in memory
+    pub fn tail_debug_log(&self, lines: usize) -> Result<Vec<String>> {
+        Ok(tail_lines(&self.debug_log_path(), lines)?)
+    }
+}
+
+#[cfg(feature = "download")]
+impl BitcoinD {
+    /// create BitcoinD struct with the downloaded executable.
+    pub fn from_downloaded() -> Result<BitcoinD> {
+        BitcoinD::new(downloaded_exe_path()?)
+    }
+    /// create BitcoinD struct with the downloaded executable and given Conf.
+    pub fn from_downloaded_with_conf(conf: &Conf) -> Result<BitcoinD> {
+        BitcoinD::with_conf(downloaded_exe_path()?, conf)
+    }
+
+    #[cfg(feature = "all_versions")]
+    /// create BitcoinD struct with the executable of the given `version`, one of
+    /// [versions::SUPPORTED_VERSIONS], installed by the build script with the `all_versions`
+    /// feature
+    pub fn from_version(version: &str) -> Result<BitcoinD> {
+        if !versions::SUPPORTED_VERSIONS.contains(&version) {
+            return Err(Error::UnsupportedVersion(version.to_string()));
+        }
+        BitcoinD::new(downloaded_version_exe_path(version)?)
+    }
+}
+
+/// The state shared by the sync and async launchers, computed from [Conf] before spawning the
+/// process
+pub(crate) struct Launch {
+    pub(crate) work_dir: DataDir,
+    pub(crate) args: Vec<String>,
+    pub(crate) view_stdout: bool,
+    pub(crate) no_wallet: bool,
+    pub(crate) fallback_fee: Option<bitcoin::FeeRate>,
+    pub(crate) rpc_url: String,
+    pub(crate) params: ConnectParams,
+}
+
+impl Launch {
+    /// Create the working directory, book the ports and build the command line args
+    pub(crate) fn new(conf: &Conf) -> Result<Launch> {
+        if conf.no_wallet && conf.initial_blocks > 0 {
+            return Err(Error::NoWallet);
+        }
+        let tmpdir = conf
+            .tmpdir
+            .clone()
+            .or_else(|| env::var("TEMPDIR_ROOT").map(PathBuf::from).ok());
+        let work_dir = match (&tmpdir, &conf.staticdir) {
+            (Some(_), Some(_)) => return Err(Error::BothDirsSpecified),
+            (Some(tmpdir), None) => DataDir::Temporary(TempDir::new_in(tmpdir)?),
+            (None, Some(workdir)) => {
+                fs::create_dir_all(workdir)?;
+                DataDir::Persistent(workdir.to_owned())
+            }
+            (None, None) => DataDir::Temporary(TempDir::new()?),
+        };
+
+        let work_dir_path = work_dir.path();
+        debug!("work_dir: {:?}", work_dir_path);
+        let cookie_file = match &conf.cookie_path {
+            Some(path) => path.clone(),
+            None => work_dir_path.join(conf.network).join(".cookie"),
+        };
+        let rpc_port = get_available_port()?;
+        let rpc_socket = SocketAddrV4::new(LOCAL_IP, rpc_port);
+        let rpc_url = format!("http://{}", rpc_socket);
+        let p2p = match conf.p2p {
+            P2P::No if !conf.addnode.is_empty() => P2P::Yes,
+            ref p2p => p2p.clone(),
+        };
+        let (p2p_args, p2p_socket) = match p2p {
+            P2P::No => (vec!["-listen=0".to_string()], None),
+            P2P::Yes => {
+                let p2p_port = get_available_port()?;
+                let p2p_socket = SocketAddrV4::new(LOCAL_IP, p2p_port);
+                let p2p_arg = format!("-port={}", p2p_port);
+                let args = vec![p2p_arg];
+                (args, Some(p2p_socket))
+            }
+            P2P::Connect(other_node_url, listen) => {
+                let p2p_port = get_available_port()?;
+                let p2p_socket = SocketAddrV4::new(LOCAL_IP, p2p_port);
+                let p2p_arg = format!("-port={}", p2p_port);
+                let connect = format!("-connect={}", other_node_url);
+                let mut args = vec![p2p_arg, connect];
+                if listen {
+                    args.push("-listen=1".to_string())
+                }
+                (args, Some(p2p_socket))
+            }
+        };
+
+        let (zmq_args, zmq_pub_raw_tx_socket, zmq_pub_raw_block_socket) = match conf.enable_zmq {
+            true => {
+                let zmq_pub_raw_tx_port = get_available_port()?;
+                let zmq_pub_raw_tx_socket = SocketAddrV4::new(LOCAL_IP, zmq_pub_raw_tx_port);
+                let zmq_pub_raw_block_port = get_available_port()?;
+                let zmq_pub_raw_block_socket = SocketAddrV4::new(LOCAL_IP, zmq_pub_raw_block_port);
+                let zmqpubrawblock_arg =
+                    format!("-zmqpubrawblock=tcp://0.0.0.0:{}", zmq_pub_raw_block_port);
+                let zmqpubrawtx_arg = format!("-zmqpubrawtx=tcp://0.0.0.0:{}", zmq_pub_raw_tx_port);
+                (
+                    vec![zmqpubrawtx_arg, zmqpubrawblock_arg],
+                    Some(zmq_pub_raw_tx_socket),
+                    Some(zmq_pub_raw_block_socket),
+                )
+            }
+            false => (vec![], None, None),
+        };
+
+        let rest_args = if conf.enable_rest {
+            vec!["-rest".to_string()]
+        } else {
+            vec![]
+        };
+
+        let datadir_arg = format!("-datadir={}", work_dir_path.display());
+        let rpc_arg = format!("-rpcport={}", rpc_port);
+        let conf_args = validate_args(conf.args.clone())?;
+
+        let mut args = vec![datadir_arg, rpc_arg];
+        args.extend(p2p_args);
+        args.extend(conf.addnode.iter().map(|addr| format!("-addnode={}", addr)));
+        args.extend(conf_args.into_iter().map(ToString::to_string));
+        args.extend(zmq_args);
+        args.extend(rest_args);
+        if let Some(accept) = conf.accept_non_std_txns {
+            check_conflicting_arg(&conf.args, "-acceptnonstdtxn")?;
+            args.push(format!("-acceptnonstdtxn={}", u8::from(accept)));
+        }
+        if let Some(rate) = conf.dust_relay_fee {
+            if !(0.0..=10.0).contains(&rate) {
+                return Err(Error::InvalidDustRelayFee(rate));
+            }
+            check_conflicting_arg(&conf.args, "-dustrelayfee")?;
+            // sat/vB to BTC/kvB
+            args.push(format!("-dustrelayfee={:.8}", rate / 100_000.0));
+        }
+        if let Some(rate) = conf.min_relay_tx_fee {
+            check_conflicting_arg(&conf.args, "-minrelaytxfee")?;
+            args.push(format!("-minrelaytxfee={:.8}", rate));
+        }
+        if let Some(mb) = conf.mempool_size_mb {
+            check_conflicting_arg(&conf.args, "-maxmempool")?;
+            args.push(format!("-maxmempool={}", mb));
+        }
+        if let Some(hash) = conf.assume_valid {
+            use bitcoin::hashes::Hash;
+            check_conflicting_arg(&conf.args, "-assumevalid")?;
+            if hash == bitcoin::BlockHash::all_zeros() {
+                args.push("-assumevalid=0".to_string());
+            } else {
+                args.push(format!("-assumevalid={}", hash));
+            }
+        }
+        if let Some(work) = &conf.min_chain_work {
+            check_conflicting_arg(&conf.args, "-minimumchainwork")?;
+            args.push(format!("-minimumchainwork={}", work));
+        }
+        if conf.blocksonly {
+            check_conflicting_arg(&conf.args, "-blocksonly")?;
+            args.push("-blocksonly".to_string());
+        }
+        if conf.reindex {
+            check_conflicting_arg(&conf.args, "-reindex")?;
+            args.push("-reindex".to_string());
+        }
+        if conf.cookie_path.is_some() {
+            check_conflicting_arg(&conf.args, "-rpccookiefile")?;
+            args.push(format!("-rpccookiefile={}", cookie_file.display()));
+        }
+
+        Ok(Launch {
+            work_dir,
+            args,
+            view_stdout: conf.view_stdout,
+            no_wallet: conf.no_wallet,
+            fallback_fee: conf
+                .args
+                .iter()
+                .find_map(|arg| arg.strip_prefix("-fallbackfee="))
+                .and_then(|rate| rate.parse().ok())
+                .map(fee_rate_from_btc_per_kvb),
+            rpc_url,
+            params: ConnectParams {
+                cookie_file,
+                rpc_socket,
+                p2p_socket,
+                zmq_pub_raw_block_socket,
+                zmq_pub_raw_tx_socket,
+                network: conf.network.to_string(),
+            },
+        })
+    }
+
+    /// The command launching the `exe` executable
+    pub(crate) fn command(&self, exe: &OsStr) -> Command {
+        let stdout = if self.view_stdout {
+            Stdio::inherit()
+        } else {
+            Stdio::null()
+        };
+        let mut command = Command::new(exe);
+        command.args(&self.args).stdout(stdout);
+        command
+    }
+
+    /// Spawn the process of the `exe` executable
+    pub(crate) fn spawn(&self, exe: &OsStr) -> Result<Child> {
+        self.command(exe)
+            .spawn()
+            .map_err(|e| Error::ProcessSpawnFailed(exe.to_string_lossy().to_string(), e))
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Spawn the process of the `exe` executable with [tokio::process::Command], killed on drop
+    pub(crate) fn spawn_async(&self, exe: &OsStr) -> Result<tokio::process::Child> {
+        tokio::process::Command::from(self.command(exe))
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Error::ProcessSpawnFailed(exe.to_string_lossy().to_string(), e))
+    }
+
+    /// Launch `exe` with `conf` and wait for the node to be ready, launching it again on early
+    /// exits while [Conf::attempts] remain. `snapshot` is copied in the network directory before
+    /// spawning
+    pub(crate) fn start(
+        exe: &OsStr,
+        conf: &Conf,
+        snapshot: Option<&Path>,
+    ) -> Result<(Launch, Child, Client)> {
+        let launch = Launch::new(conf)?;
+        if let Some(snapshot) = snapshot {
+            let network_dir = launch.work_dir.path().join(conf.network);
+            copy_dir(snapshot, &network_dir)
+                .with_context(|| format!("cannot copy snapshot {:?}", snapshot))?;
+        }
+
+        debug!("launching {:?} with args: {:?}", exe, launch.args);
+
+        let mut process = launch.spawn(exe)?;
+
+        let mut i = 0;
+        // wait bitcoind is ready, use default wallet
+        let client = loop {
+            thread::sleep(Duration::from_millis(100));
+            assert!(process.stderr.is_none());
+            match launch.step(process.try_wait()?, conf.attempts)? {
+                Step::Ready(client) => break client,
+                Step::NotReady => debug!(
+                    "bitcoin client for process {} not ready ({})",
+                    process.id(),
+                    i
+                ),
+                Step::Relaunch => {
+                    let mut conf = conf.clone();
+                    conf.attempts -= 1;
+                    return Ok(Launch::start(exe, &conf, snapshot)
+                        .with_context(|| format!("Remaining attempts {}", conf.attempts))?);
+                }
+            }
+            i += 1;
+        };
+
+        launch.log_started(process.id());
+        Ok((launch, process, client))
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Async version of [Launch::start] without snapshot, the process is spawned with
+    /// [tokio::process::Command] and waited with [tokio::time::sleep]
+    pub(crate) async fn start_async(
+        exe: &OsStr,
+        conf: &Conf<'_>,
+    ) -> Result<(Launch, tokio::process::Child, Client)> {
+        let mut attempts = conf.attempts;
+        loop {
+            let started = Launch::start_attempt_async(exe, conf, attempts).await;
+            // like the recursion of the sync version, relaunch errors report the remaining attempts
+            let started = if attempts < conf.attempts {
+                started.with_context(|| format!("Remaining attempts {}", attempts))?
+            } else {
+                started?
+            };
+            match started {
+                Some(started) => return Ok(started),
+                None => attempts -= 1,
+            }
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    /// A single attempt of [Launch::start_async], `None` if the node exited early and must be
+    /// launched again
+    async fn start_attempt_async(
+        exe: &OsStr,
+        conf: &Conf<'_>,
+        attempts: u8,
+    ) -> Result<Option<(Launch, tokio::process::Child, Client)>> {
+        let launch = Launch::new(conf)?;
+
+        debug!("launching {:?} with args: {:?}", exe, launch.args);
+
+        let mut process = launch.spawn_async(exe)?;
+
+        let mut i = 0;
+        // wait bitcoind is ready, use default wallet
+        let client = loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            match launch.step_async(process.try_wait()?, attempts).await? {
+                Step::Ready(client) => break client,
+                Step::NotReady => debug!(
+                    "bitcoin client for process {:?} not ready ({})",
+                    process.id(),
+                    i
+                ),
+                Step::Relaunch => return Ok(None),
+            }
+            i += 1;
+        };
+
+        launch.log_started(process.id().unwrap_or_default());
+        Ok(Some((launch, process, client)))
+    }
+
+    /// One iteration of the startup loop, given the `try_wait` result of the process: either the
+    /// node exited early or it's checked for readiness.
+    ///
+    /// An early exit with no `attempts` remaining is an [Error::EarlyExit]
+    fn step(&self, exit: Option<ExitStatus>, attempts: u8) -> Result<Step> {
+        if let Some(status) = exit {
+            return self.early_exit(status, attempts);
+        }
+        let ready = ready_client(&self.rpc_url, &self.params.cookie_file, self.no_wallet)?;
+        Ok(ready.map_or(Step::NotReady, Step::Ready))
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Async version of [Launch::step], the blocking rpc calls checking readiness are made on the
+    /// blocking thread pool so that the async runtime isn't blocked
+    async fn step_async(&self, exit: Option<ExitStatus>, attempts: u8) -> Result<Step> {
+        if let Some(status) = exit {
+            return self.early_exit(status, attempts);
+        }
+        let rpc_url = self.rpc_url.clone();
+        let cookie_file = self.params.cookie_file.clone();
+        let no_wallet = self.no_wallet;
+        let ready =
+            tokio::task::spawn_blocking(move || ready_client(&rpc_url, &cookie_file, no_wallet))
+                .await
+                .context("readiness check panicked")??;
+        Ok(ready.map_or(Step::NotReady, Step::Ready))
+    }
+
+    /// Handle the early exit of the process, [Step::Relaunch] if `attempts` remain
+    fn early_exit(&self, status: ExitStatus, attempts: u8) -> Result<Step> {
+        let network = &self.params.network;
+        if attempts > 0 {
+            warn!("early exit of {} node with: {:?}. Trying to launch again ({} attempts remaining), maybe some other process used our available port", network, status, attempts);
+            Ok(Step::Relaunch)
+        } else {
+            error!("early exit of {} node with: {:?}", network, status);
+            Err(Error::EarlyExit(status))
+        }
+    }
+
+    /// Log the connection parameters of the started process with the given `pid`
+    pub(crate) fn log_started(&self, pid: u32) {
+        // single json line to easily correlate logs of different nodes, eg. with `jq`
+        debug!(
+            "{}",
+            json!({
+                "pid": pid,
+                "rpc_socket": self.params.rpc_socket.to_string(),
+                "p2p_socket": self.params.p2p_socket.map(|s| s.to_string()),
+                "zmq_pub_raw_block_socket": self.params.zmq_pub_raw_block_socket.map(|s| s.to_string()),
+                "zmq_pub_raw_tx_socket": self.params.zmq_pub_raw_tx_socket.map(|s| s.to_string()),
+                "work_dir": self.work_dir.path().display().to_string(),
+            })
+        );
+    }
+}
+
+/// Mine `blocks` blocks with `client` to an address of its wallet, see [Conf::initial_blocks]
+fn mine_initial_blocks(client: &Client, blocks: u64) -> Result<()> {
+    if blocks > 0 {
+        let address = client.get_new_address(None, None)?.assume_checked();
+        client.generate_to_address(blocks, &address)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+/// Async version of [mine_initial_blocks] taking and returning the `client`, the rpc calls are
+/// made on the blocking thread pool so that the async runtime isn't blocked
+async fn mine_initial_blocks_async(client: Client, blocks: u64) -> Result<Client> {
+    if blocks == 0 {
+        return Ok(client);
+    }
+    tokio::task::spawn_blocking(move || mine_initial_blocks(&client, blocks).map(|_| client))
+        .await
+        .context("mining the initial blocks panicked")?
+}
+
+/// The outcome of an iteration of the startup loop, see [Launch::step]
+enum Step {
+    /// The node is ready, the client is connected to the default wallet unless [Conf::no_wallet]
+    Ready(Client),
+    /// The node is still starting
+    NotReady,
+    /// The node exited early and must be launched again
+    Relaunch,
+}
+
+/// Returns a client connected to the default wallet if the node at `rpc_url` is ready to accept
+/// rpc calls
+pub(crate) fn ready_client(
+    rpc_url: &str,
+    cookie_file: &Path,
+    no_wallet: bool,
+) -> Result<Option<Client>> {
+    let client_base = match Client::new(rpc_url, Auth::CookieFile(cookie_file.to_path_buf())) {
+        Ok(client_base) => client_base,
+        Err(_) => return Ok(None),
+    };
+    // RpcApi has get_blockchain_info method, however being generic with `Value` allows
+    // to be compatible with different version, in the end we are only interested if
+    // the call is succesfull not in the returned value.
+    if client_base.call::<Value>("getblockchaininfo", &[]).is_err() {
+        return Ok(None);
+    }
+    if no_wallet {
+        return Ok(Some(client_base));
+    }
+    // Try creating new wallet, if fails due to already existing wallet file
+    // try loading the same. Return if still errors.
+    if client_base
+        .create_wallet("default", None, None, None, None)
+        .is_err()
+    {
+        client_base
+            .load_wallet("default")
+            .map_err(|inner| Error::WalletError {
+                wallet: "default".to_string(),
+                inner,
+            })?;
+    }
+    let node_url_default = format!("{}/wallet/default", rpc_url);
+    Ok(Some(Client::new(
+        &node_url_default,
+        Auth::CookieFile(cookie_file.to_path_buf()),
+    )?))
+}
+
+#[cfg(feature = "tokio")]
+/// How long dropping a node spawned with [tokio::process::Command] and a persistent datadir waits
+/// for the node to stop before killing it
+const ASYNC_DROP_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The bitcoind process handle, spawned with [tokio::process::Command] by
+/// [BitcoinD::with_conf_async]
+#[derive(Debug)]
+enum Process {
+    Std(Child),
+    #[cfg(feature = "tokio")]
+    Tokio(tokio::process::Child),
+}
+
+impl Process {
+    fn id(&self) -> u32 {
+        match self {
+            Process::Std(child) => child.id(),
+            #[cfg(feature = "tokio")]
+            Process::Tokio(child) => child.id().unwrap_or_default(),
+        }
+    }
+
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        match self {
+            Process::Std(child) => child.try_wait(),
+            #[cfg(feature = "tokio")]
+            Process::Tokio(child) => child.try_wait(),
+        }
+    }
+
+    /// Wait for the process to exit, blocking the thread also for [Process::Tokio]
+    fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        match self {
+            Process::Std(child) => child.wait(),
+            #[cfg(feature = "tokio")]
+            Process::Tokio(child) => loop {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(status);
+                }
+                thread::sleep(Duration::from_millis(100));
+            },
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Wait up to `timeout` for the process to exit, `None` if it's still running
+    fn wait_timeout(&mut self, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() > deadline {
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    fn kill(&mut self) -> std::io::Result<()> {
+        match self {
+            Process::Std(child) => child.kill(),
+            #[cfg(feature = "tokio")]
+            Process::Tokio(child) => child.start_kill(),
+        }
+    }
+}
+
+impl Drop for BitcoinD {
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) && !self.stop_called && !thread::panicking() {
+            // panicking would break every test relying on drop for cleanup, so it's opt-in
+            if env::var_os("BITCOIND_REQUIRE_STOP").is_some() {
+                panic!(
+                    "BitcoinD dropped without calling stop(), pid {}",
+                    self.process.id()
+                );
+            }
+            debug!("BitcoinD dropped without calling stop(), killing it");
+        }
+        if let DataDir::Persistent(_) = self.work_dir {
+            match self.process {
+                Process::Std(_) => {
+                    let _ = self.stop();
+                }
+                // drop can't be async, bound the time the runtime is blocked if the node hangs
+                // on shutdown, it's killed afterwards
+                #[cfg(feature = "tokio")]
+                Process::Tokio(_) => {
+                    if self.client.stop().is_ok() {
+                        let _ = self.process.wait_timeout(ASYNC_DROP_STOP_TIMEOUT);
+                    }
+                }
+            }
+        }
+        let _ = self.process.kill();
+    }
+}
+
+/// Sum of the size of the files in `path` and its subdirectories, symlinks are not followed
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Recursively copy the content of the `from` directory in the `to` directory, created if missing
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the last `lines` lines of the file at `path`, reading chunks backward from the end
+fn tail_lines(path: &Path, lines: usize) -> std::io::Result<Vec<String>> {
+    const CHUNK_SIZE: u64 = 4096;
+    let mut file = fs::File::open(path)?;
+    let mut pos = file.metadata()?.len();
+    let mut buf: Vec<u8> = vec![];
+    // the last line ends with a newline too, so one more newline is needed to have `lines` whole
+    // lines
+    while pos > 0 && buf.iter().filter(|b| **b == b'\n').count() <= lines {
+        let read = CHUNK_SIZE.min(pos);
+        pos -= read;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk = vec![0; read as usize];
+        file.read_exact(&mut chunk)?;
+        chunk.extend(buf);
+        buf = chunk;
+    }
+    let content = String::from_utf8_lossy(&buf);
+    let all: Vec<&str> = content.lines().collect();
+    let skip = all.len().saturating_sub(lines);
+    Ok(all[skip..].iter().map(ToString::to_string).collect())
+}
+
+/// Returns a non-used local port if available.
+///
+/// Note there is a race condition during the time the method check availability and the caller
+pub fn get_available_port() -> Result<u16> {
+    // using 0 as port let the system assign a port available
+    let t = TcpListener::bind(("127.0.0.1", 0)).map_err(|_| Error::PortUnavailable(0))?; // 0 means the OS choose a free port
+    t.local_addr()
+        .map(|s| s.port())
+        .map_err(|_| Error::PortUnavailable(0))
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<bitcoincore_rpc::Error> for Error {
+    fn from(e: bitcoincore_rpc::Error) -> Self {
+        Error::Rpc(e)
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Error::Other(e)
+    }
+}
+
+/// Provide the bitcoind executable path if a version feature has been specified
+///
+/// Without a version feature it always returns [Error::NoFeature], so that it can be used in a
+/// fallback chain like `downloaded_exe_path().or_else(|_| ...)` regardless of the enabled features
+#[cfg(not(feature = "download"))]
+pub fn downloaded_exe_path() -> Result<String> {
+    Err(Error::NoFeature)
+}
+
+/// Provide the bitcoind executable path if a version feature has been specified
+///
+/// Returns [Error::SkipDownload] if the `BITCOIND_SKIP_DOWNLOAD` env var is set
+#[cfg(feature = "download")]
+pub fn downloaded_exe_path() -> Result<String> {
+    downloaded_version_exe_path(versions::VERSION)
+}
+
+/// Provide the path of the bitcoind executable of the given `version`, downloaded by the build
+/// script
+#[cfg(feature = "download")]
+fn downloaded_version_exe_path(version: &str) -> Result<String> {
+    if std::env::var_os("BITCOIND_SKIP_DOWNLOAD").is_some() {
+        return Err(Error::SkipDownload);
+    }
+
+    let mut path: PathBuf = env!("OUT_DIR").into();
+    path.push("bitcoin");
+    path.push(format!("bitcoin-{}", version));
+    path.push("bin");
+
+    Ok(format!("{}", exe_in_dir(path).display()))
+}
+
+/// Returns the version corresponding to the given feature, eg. `"0.17.1"` for `"0_17_1"` or
+/// `"26.0"` for `"26_0"`, `None` if the feature isn't a known version
+pub fn version_from_feature(feature: &str) -> Option<&'static str> {
+    versions::SUPPORTED_VERSIONS
+        .iter()
+        .find(|v| v.replace('.', "_") == feature)
+        .copied()
+}
+
+/// Returns the daemon `bitcoind` executable with the following precedence:
+///
+/// 1) If it's specified in the `BITCOIND_EXE` env var
+/// 2) If a directory is specified in the `BITCOIND_EXE_DIR` env var, the `bitcoind` executable
+///    (`bitcoind.exe` on windows) in that directory
+/// 3) If there are no env vars but an auto-download feature such as `23_1` is enabled, returns
+///    the path of the downloaded executable
+/// 4) If neither of the precedent are available, the `bitcoind` executable is searched in the `PATH`
+pub fn exe_path() -> Result<String> {
+    if let Ok(path) = std::env::var("BITCOIND_EXE") {
+        return Ok(path);
+    }
+    if let Some(dir) = std::env::var_os("BITCOIND_EXE_DIR") {
+        return Ok(exe_in_dir(dir).display().to_string());
+    }
+    if let Ok(path) = downloaded_exe_path() {
+        return Ok(path);
+    }
+    which::which(format!("bitcoind{}", exe_suffix()))
+        .map_err(|_| Error::NoBitcoindExecutableFound)
+        .map(|p| p.display().to_string())
+}
+
+/// Returns the shared chain state snapshot specified in the `BITCOIND_SNAPSHOT` env var, to be
+/// used with [BitcoinD::with_snapshot]
+pub fn snapshot_path() -> Option<PathBuf> {
+    std::env::var_os("BITCOIND_SNAPSHOT").map(PathBuf::from)
+}
+
+/// Convert a fee rate in BTC/kvB, the unit of Bitcoin Core options and RPCs, in a [bitcoin::FeeRate]
+fn fee_rate_from_btc_per_kvb(rate: f64) -> bitcoin::FeeRate {
+    // 1 vB is 4 weight units
+    bitcoin::FeeRate::from_sat_per_kwu((rate * 100_000_000.0 / 4.0).round() as u64)
+}
+
+/// The network corresponding to the name used in [Conf::network]
+fn network_from_name(name: &str) -> Option<bitcoin::Network> {
+    match name {
+        "" | "main" => Some(bitcoin::Network::Bitcoin),
+        "testnet" | "testnet3" => Some(bitcoin::Network::Testnet),
+        "signet" => Some(bitcoin::Network::Signet),
+        "regtest" => Some(bitcoin::Network::Regtest),
+        _ => None,
+    }
+}
+
+/// The suffix of executable file names on the target platform, `.exe` on windows
+fn exe_suffix() -> &'static str {
+    if cfg!(target_os = "windows") {
+        ".exe"
+    } else {
+        ""
+    }
+}
+
+/// The path of the `bitcoind` executable in `dir`, `bitcoind.exe` on windows
+fn exe_in_dir<P: Into<PathBuf>>(dir: P) -> PathBuf {
+    let mut path = dir.into();
+    path.push(format!("bitcoind{}", exe_suffix()));
+    path
+}
+
+/// Returned for unknown transactions, addresses and keys
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+
+/// Returned for wallet calls when the wallet isn't loaded
+const RPC_WALLET_NOT_FOUND: i32 = -18;
+
+/// Returned for wallet calls without a wallet in the url when more wallets are loaded
+const RPC_WALLET_NOT_SPECIFIED: i32 = -19;
+
+/// Returned by nodes not supporting the called method
+const RPC_METHOD_NOT_FOUND: i32 = -32601;
+
+/// Whether `error` is an error returned by the node with the given RPC error `code`
+fn is_rpc_error(error: &bitcoincore_rpc::Error, code: i32) -> bool {
+    matches!(
+        error,
+        bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(e)) if e.code == code
+    )
+}
+
+/// Whether `error` is the RPC error returned by nodes not supporting the called method, used to
+/// fall back to older RPCs
+fn is_method_not_found(error: &bitcoincore_rpc::Error) -> bool {
+    is_rpc_error(error, RPC_METHOD_NOT_FOUND)
+}
+
+/// Returns an error if `arg` is in `args`, used for args set by dedicated [Conf] fields
+fn check_conflicting_arg(args: &[&str], arg: &'static str) -> Result<(), Error> {
+    let conflicting = args
+        .iter()
+        .any(|a| *a == arg || a.strip_prefix(arg).map_or(false, |v| v.starts_with('=')));
+    if conflicting {
+        Err(Error::ConflictingArg(arg))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate the specified arg if there is any unavailable or deprecated one
+pub fn validate_args(args: Vec<&str>) -> Result<Vec<&str>> {
+    args.iter().try_for_each(|arg| {
+        // other kind of invalid arguments can be added into the list if needed
+        if INVALID_ARGS.iter().any(|x| arg.starts_with(x)) {
+            return Err(Error::RpcUserAndPasswordUsed);
+        }
+        Ok(())
+    })?;
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitcoincore_rpc::bitcoin::hashes::Hash;
+    use crate::bitcoincore_rpc::bitcoin::{transaction, Amount, BlockHash, Network, Psbt};
+    use crate::bitcoincore_rpc::jsonrpc::serde_json::{Map, Value};
+    use crate::bitcoincore_rpc::{Auth, Client};
+    use crate::exe_path;
+    use crate::{
+        exe_in_dir, get_available_port, tail_lines, version_from_feature, BitcoinD, Conf, ConfFile,
+        ConnectParams, CookieError, DataDir, Error, LOCAL_IP, P2P,
+    };
+    use anyhow::Context;
+    use bitcoincore_rpc::RpcApi;
+    use std::net::SocketAddrV4;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_local_ip() {
+        assert_eq!("127.0.0.1", format!("{}", LOCAL_IP));
+        let port = get_available_port().unwrap();
+        let socket = SocketAddrV4::new(LOCAL_IP, port);
+        assert_eq!(format!("127.0.0.1:{}", port), format!("{}", socket));
+    }
+
+    #[test]
+    fn test_conf_file() {
+        let mut conf = Conf::default();
+        conf.args.push("-txindex");
+        conf.args.push("-listen=0");
+        conf.args.push("-addnode=127.0.0.1:1");
+        conf.args.push("-addnode=127.0.0.1:2");
+        let content = conf.to_conf_file();
+        assert_eq!(
+            content,
+            "regtest=1\n[regtest]\nfallbackfee=0.0001\ntxindex=1\nlisten=0\naddnode=127.0.0.1:1\naddnode=127.0.0.1:2\n"
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bitcoin.conf");
+        std::fs::write(&path, &content).unwrap();
+        let parsed = ConfFile::read(&path).unwrap();
+        assert_eq!(parsed.network, "regtest");
+        assert_eq!(
+            parsed.args,
+            vec![
+                "-regtest",
+                "-fallbackfee=0.0001",
+                "-txindex=1",
+                "-listen=0",
+                "-addnode=127.0.0.1:1",
+                "-addnode=127.0.0.1:2"
+            ]
+        );
+
+        std::fs::write(&path, Conf::default().to_conf_file()).unwrap();
+        assert_eq!(ConfFile::read(&path).unwrap().conf(), Conf::default());
+
+        std::fs::write(&path, "# comment\nchain=signet\ntxindex\n").unwrap();
+        assert!(ConfFile::read(&path).is_err());
+    }
+
+    #[test]
+    fn test_bitcoind() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let info = bitcoind.client.get_blockchain_info().unwrap();
+        assert_eq!(0, info.blocks);
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let _ = bitcoind.client.generate_to_address(1, &address).unwrap();
+        let info = bitcoind.client.get_blockchain_info().unwrap();
+        assert_eq!(1, info.blocks);
+    }
+
+    #[test]
+    #[cfg(feature = "0_21_2")]
+    fn test_getindexinfo() {
+        let exe = init();
+        let mut conf = Conf::default();
+        conf.args.push("-txindex");
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert!(
+            bitcoind.client.version().unwrap() >= 210_000,
+            "getindexinfo requires bitcoin >0.21"
+        );
+        let info: std::collections::HashMap<String, bitcoincore_rpc::jsonrpc::serde_json::Value> =
+            bitcoind.client.call("getindexinfo", &[]).unwrap();
+        assert!(info.contains_key("txindex"));
+        assert!(bitcoind.client.version().unwrap() >= 210_000);
+    }
+
+    #[test]
+    fn test_p2p() {
+        let exe = init();
+        let mut conf = Conf::default();
+        conf.p2p = P2P::Yes;
+
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert_eq!(peers_connected(&bitcoind.client), 0);
+        let mut other_conf = Conf::default();
+        other_conf.p2p = bitcoind.p2p_connect(false).unwrap();
+
+        let other_bitcoind = BitcoinD::with_conf(&exe, &other_conf).unwrap();
+        assert_eq!(peers_connected(&bitcoind.client), 1);
+        assert_eq!(peers_connected(&other_bitcoind.client), 1);
+    }
+
+    #[cfg(not(target_os = "windows"))] // TODO: investigate why it doesn't work in windows
+    #[test]
+    fn test_data_persistence() {
+        // Create a Conf with staticdir type
+        let mut conf = Conf::default();
+        let datadir = TempDir::new().unwrap();
+        conf.staticdir = Some(datadir.path().to_path_buf());
+
+        // Start BitcoinD with persistent db config
+        // Generate 101 blocks
+        // Wallet balance should be 50
+        let bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        let core_addrs = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind
+            .client
+            .generate_to_address(101, &core_addrs)
+            .unwrap();
+        let wallet_balance_1 = bitcoind.client.get_balance(None, None).unwrap();
+        let best_block_1 = bitcoind.client.get_best_block_hash().unwrap();
+
+        drop(bitcoind);
+
+        // Start a new BitcoinD with the same datadir
+        let bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+
+        let wallet_balance_2 = bitcoind.client.get_balance(None, None).unwrap();
+        let best_block_2 = bitcoind.client.get_best_block_hash().unwrap();
+
+        // Check node chain data persists
+        assert_eq!(best_block_1, best_block_2);
+
+        // Check the node wallet balance persists
+        assert_eq!(wallet_balance_1, wallet_balance_2);
+    }
+
+    #[test]
+    fn test_multi_p2p() {
+        let _ = env_logger::try_init();
+        let mut conf_node1 = Conf::default();
+        conf_node1.p2p = P2P::Yes;
+        let node1 = BitcoinD::with_conf(exe_path().unwrap(), &conf_node1).unwrap();
+
+        // Create Node 2 connected Node 1
+        let mut conf_node2 = Conf::default();
+        conf_node2.p2p = node1.p2p_connect(true).unwrap();
+        let node2 = BitcoinD::with_conf(exe_path().unwrap(), &conf_node2).unwrap();
+
+        // Create Node 3 Connected To Node
+        let mut conf_node3 = Conf::default();
+        conf_node3.p2p = node2.p2p_connect(false).unwrap();
+        let node3 = BitcoinD::with_conf(exe_path().unwrap(), &conf_node3).unwrap();
+
+        // Get each nodes Peers
+        let node1_peers = peers_connected(&node1.client);
+        let node2_peers = peers_connected(&node2.client);
+        let node3_peers = peers_connected(&node3.client);
+
+        // Peers found
+        assert!(node1_peers >= 1);
+        assert!(node2_peers >= 1);
+        assert_eq!(node3_peers, 1, "listen false but more than 1 peer");
+    }
+
+    #[cfg(any(feature = "0_19_1", not(feature = "download")))]
+    #[test]
+    fn test_multi_wallet() {
+        use bitcoincore_rpc::bitcoin::Amount;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let alice = bitcoind.create_wallet("alice").unwrap();
+        let alice_address = alice.get_new_address(None, None).unwrap().assume_checked();
+        let bob = bitcoind.create_wallet("bob").unwrap();
+        let bob_address = bob.get_new_address(None, None).unwrap().assume_checked();
+        bitcoind
+            .client
+            .generate_to_address(1, &alice_address)
+            .unwrap();
+        bitcoind
+            .client
+            .generate_to_address(101, &bob_address)
+            .unwrap();
+        assert_eq!(
+            Amount::from_btc(50.0).unwrap(),
+            alice.get_balances().unwrap().mine.trusted
+        );
+        assert_eq!(
+            Amount::from_btc(50.0).unwrap(),
+            bob.get_balances().unwrap().mine.trusted
+        );
+        assert_eq!(
+            Amount::from_btc(5000.0).unwrap(),
+            bob.get_balances().unwrap().mine.immature
+        );
+        let _txid = alice
+            .send_to_address(
+                &bob_address,
+                Amount::from_btc(1.0).unwrap(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(
+            alice.get_balances().unwrap().mine.trusted < Amount::from_btc(49.0).unwrap()
+                && alice.get_balances().unwrap().mine.trusted > Amount::from_btc(48.9).unwrap()
+        );
+
+        // bob wallet may not be immediately updated
+        for _ in 0..30 {
+            if bob.get_balances().unwrap().mine.untrusted_pending.to_sat() > 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        assert_eq!(
+            Amount::from_btc(1.0).unwrap(),
+            bob.get_balances().unwrap().mine.untrusted_pending
+        );
+        assert!(
+            bitcoind.create_wallet("bob").is_err(),
+            "wallet already exist"
+        );
+    }
+
+    #[cfg(any(feature = "0_18_1", not(feature = "download")))]
+    #[test]
+    fn test_derive_addresses() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.client.get_new_address(None, None).unwrap();
+        let descriptor = format!("addr({})", address.clone().assume_checked());
+        let descriptor = bitcoind
+            .client
+            .get_descriptor_info(&descriptor)
+            .unwrap()
+            .descriptor;
+        let derived = bitcoind.derive_addresses(&descriptor, None).unwrap();
+        assert_eq!(derived, vec![address]);
+    }
+
+    #[cfg(any(feature = "23_1", not(feature = "download")))]
+    #[test]
+    fn test_import_descriptors() {
+        use crate::ImportDescriptorRequest;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        bitcoind
+            .client
+            .create_wallet("watch", Some(true), None, None, None)
+            .unwrap();
+        let watch = Client::new(
+            &bitcoind.rpc_url_with_wallet("watch"),
+            Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+        )
+        .unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let descriptor = bitcoind
+            .client
+            .get_descriptor_info(&format!("addr({})", address))
+            .unwrap()
+            .descriptor;
+        let valid = ImportDescriptorRequest {
+            descriptor,
+            timestamp: 0,
+            range: None,
+            watchonly: true,
+        };
+        let invalid = ImportDescriptorRequest {
+            descriptor: format!("addr({})#00000000", address),
+            ..valid.clone()
+        };
+        let result = bitcoind
+            .import_descriptors(&watch, &[valid, invalid])
+            .unwrap();
+        assert_eq!(result, vec![true, false]);
+    }
+
+    #[test]
+    fn test_list_utxos() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.client.get_new_address(None, None).unwrap();
+        bitcoind
+            .client
+            .generate_to_address(101, &address.clone().assume_checked())
+            .unwrap();
+        let utxos = bitcoind.list_utxos(&bitcoind.client, 1).unwrap();
+        assert_eq!(utxos.len(), 1, "only the first coinbase is mature");
+        assert_eq!(utxos[0].amount.to_btc(), 50.0);
+        assert_eq!(utxos[0].address, Some(address));
+    }
+
+    #[test]
+    fn test_lock_utxo() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let utxo = bitcoind.list_utxos(&bitcoind.client, 1).unwrap().remove(0);
+        assert!(bitcoind.list_locked_utxos().unwrap().is_empty());
+
+        bitcoind.lock_utxo(&utxo.txid, utxo.vout).unwrap();
+        assert_eq!(
+            bitcoind.list_locked_utxos().unwrap(),
+            vec![(utxo.txid, utxo.vout)]
+        );
+        assert!(bitcoind.list_utxos(&bitcoind.client, 1).unwrap().is_empty());
+
+        bitcoind.unlock_utxo(&utxo.txid, utxo.vout).unwrap();
+        assert!(bitcoind.list_locked_utxos().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_send_to_address() {
+        use crate::SendToAddressOptions;
+        use bitcoincore_rpc::bitcoin::Amount;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_btc(1.0).unwrap())
+            .unwrap();
+        assert!(bitcoind.client.get_raw_mempool().unwrap().contains(&txid));
+
+        let opts = SendToAddressOptions {
+            subtract_fee: Some(true),
+            ..Default::default()
+        };
+        let txid = bitcoind
+            .send_to_address_with_opts(&address, Amount::from_btc(1.0).unwrap(), &opts)
+            .unwrap();
+        let tx = bitcoind.client.get_raw_transaction(&txid, None).unwrap();
+        let output = tx
+            .output
+            .iter()
+            .find(|o| o.script_pubkey == address.script_pubkey())
+            .unwrap();
+        assert!(
+            output.value < Amount::from_btc(1.0).unwrap(),
+            "fee is subtracted"
+        );
+    }
+
+    #[cfg(any(feature = "0_19_1", not(feature = "download")))]
+    #[test]
+    fn test_sweep_wallet() {
+        use bitcoincore_rpc::bitcoin::Amount;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let bob = bitcoind.create_wallet("bob").unwrap();
+        let bob_address = bob.get_new_address(None, None).unwrap().assume_checked();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+        bitcoind
+            .client
+            .generate_to_address(1, &bob_address)
+            .unwrap();
+        bitcoind.client.generate_to_address(100, &address).unwrap();
+        let fifty = Amount::from_btc(50.0).unwrap();
+        assert_eq!(bitcoind.client.get_balance(None, None).unwrap(), fifty);
+        assert_eq!(bob.get_balance(None, None).unwrap(), fifty);
+
+        assert!(bitcoind.sweep_wallet(&bob_address).unwrap().is_some());
+        assert_eq!(
+            bitcoind.client.get_balance(None, None).unwrap(),
+            Amount::ZERO
+        );
+        assert!(bitcoind.sweep_wallet(&bob_address).unwrap().is_none());
+
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+        let bob_balance = bob.get_balance(None, None).unwrap();
+        assert!(bob_balance > Amount::from_btc(99.9).unwrap() && bob_balance < fifty * 2);
+    }
+
+    #[test]
+    fn test_rescan_blockchain() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(10, &address).unwrap();
+        let stop_height = bitcoind
+            .rescan_blockchain(&bitcoind.client, Some(5))
+            .unwrap();
+        assert_eq!(stop_height, 10);
+    }
+
+    #[test]
+    fn test_get_tx() {
+        use bitcoincore_rpc::bitcoin::Amount;
+        let exe = init();
+        let mut conf = Conf::default();
+        conf.args.push("-txindex");
+        let bitcoind = BitcoinD::with_conf(exe, &conf).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_btc(1.0).unwrap())
+            .unwrap();
+        assert_eq!(bitcoind.get_tx(&txid).unwrap().compute_txid(), txid);
+
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+        assert_eq!(bitcoind.get_tx(&txid).unwrap().compute_txid(), txid);
+    }
+
+    #[test]
+    fn test_get_tx_confirmations() {
+        use bitcoincore_rpc::bitcoin::{Amount, Txid};
+        let exe = init();
+        let mut conf = Conf::default();
+        conf.args.push("-txindex");
+        let bitcoind = BitcoinD::with_conf(exe, &conf).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_btc(1.0).unwrap())
+            .unwrap();
+        assert_eq!(bitcoind.get_tx_confirmations(&txid).unwrap(), None);
+
+        bitcoind.client.generate_to_address(2, &address).unwrap();
+        assert_eq!(bitcoind.get_tx_confirmations(&txid).unwrap(), Some(2));
+
+        let unknown: Txid = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let err = bitcoind.get_tx_confirmations(&unknown).unwrap_err();
+        assert!(err.to_string().contains("unknown to the node"));
+    }
+
+    #[test]
+    fn test_get_chain_tips() {
+        use crate::ChainTipStatus;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let other_address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let stale = bitcoind.client.generate_to_address(3, &address).unwrap();
+        assert_eq!(bitcoind.get_chain_tips().unwrap().len(), 1);
+
+        // reorg the last 2 blocks with a longer branch
+        bitcoind.client.invalidate_block(&stale[1]).unwrap();
+        let active = bitcoind
+            .client
+            .generate_to_address(3, &other_address)
+            .unwrap();
+        bitcoind.client.reconsider_block(&stale[1]).unwrap();
+
+        let tips = bitcoind.get_chain_tips().unwrap();
+        assert_eq!(tips.len(), 2);
+        let active_tip = tips
+            .iter()
+            .find(|t| t.status == ChainTipStatus::Active)
+            .unwrap();
+        assert_eq!((active_tip.height, active_tip.hash), (4, active[2]));
+        let stale_tip = tips
+            .iter()
+            .find(|t| t.status == ChainTipStatus::ValidFork)
+            .unwrap();
+        assert_eq!(
+            (stale_tip.height, stale_tip.hash, stale_tip.branch_length),
+            (3, stale[2], 2)
+        );
+    }
+
+    #[test]
+    fn test_ban_peer() {
+        let exe = init();
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let other_conf = Conf {
+            p2p: bitcoind.p2p_connect(false).unwrap(),
+            ..Conf::default()
+        };
+        let _other_bitcoind = BitcoinD::with_conf(&exe, &other_conf).unwrap();
+        assert!(wait_for_peers_connected(&bitcoind.client, 1));
+        assert!(bitcoind.list_banned().unwrap().is_empty());
+
+        bitcoind.ban_peer("127.0.0.1", Some(60)).unwrap();
+        assert_eq!(bitcoind.list_banned().unwrap(), vec!["127.0.0.1/32"]);
+        assert!(wait_for_peers_connected(&bitcoind.client, 0));
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        assert_eq!(
+            peers_connected(&bitcoind.client),
+            0,
+            "banned peer reconnected"
+        );
+    }
+
+    #[test]
+    fn test_disconnect_peer() {
+        use crate::PeerRef;
+        let exe = init();
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let other_bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let other_socket = other_bitcoind.params.p2p_socket.unwrap();
+
+        // `onetry` doesn't reconnect after the disconnection
+        bitcoind
+            .client
+            .onetry_node(&other_socket.to_string())
+            .unwrap();
+        assert!(wait_for_peers_connected(&bitcoind.client, 1));
+        bitcoind
+            .disconnect_peer(&PeerRef::Address(other_socket))
+            .unwrap();
+        assert!(wait_for_peers_connected(&bitcoind.client, 0));
+
+        bitcoind
+            .client
+            .onetry_node(&other_socket.to_string())
+            .unwrap();
+        assert!(wait_for_peers_connected(&bitcoind.client, 1));
+        let id = bitcoind.client.get_peer_info().unwrap()[0].id;
+        bitcoind.disconnect_peer(&PeerRef::Id(id)).unwrap();
+        assert!(wait_for_peers_connected(&bitcoind.client, 0));
+        assert!(wait_for_peers_connected(&other_bitcoind.client, 0));
+    }
+
+    #[test]
+    fn test_add_node() {
+        let exe = init();
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let other_bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let other_socket = other_bitcoind.params.p2p_socket.unwrap();
+        assert!(bitcoind.list_added_nodes().unwrap().is_empty());
+
+        bitcoind.add_node(&other_socket).unwrap();
+        assert_eq!(bitcoind.list_added_nodes().unwrap(), vec![other_socket]);
+        assert!(wait_for_peers_connected(&bitcoind.client, 1));
+
+        bitcoind.remove_node(&other_socket).unwrap();
+        assert!(bitcoind.list_added_nodes().unwrap().is_empty());
+        assert!(bitcoind.remove_node(&other_socket).is_err());
+    }
+
+    #[test]
+    fn test_validate_address() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let info = bitcoind.validate_address(&address.to_string()).unwrap();
+        assert!(info.is_valid);
+        assert_eq!(info.address, Some(address.to_string()));
+        assert_eq!(
+            info.script_pub_key,
+            Some(address.script_pubkey().to_hex_string())
+        );
+        assert_eq!(info.is_mine, Some(true));
+        assert_eq!(info.is_watch_only, Some(false));
+
+        let info = bitcoind.validate_address("notanaddress").unwrap();
+        assert!(!info.is_valid);
+        assert_eq!(info.address, None);
+        assert_eq!(info.is_mine, None);
+
+        // validation doesn't need a wallet
+        let conf = Conf {
+            no_wallet: true,
+            ..Conf::default()
+        };
+        let no_wallet = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        let info = no_wallet.validate_address(&address.to_string()).unwrap();
+        assert!(info.is_valid);
+        assert_eq!(info.is_mine, None);
+
+        bitcoind.client.unload_wallet(Some("default")).unwrap();
+        let info = bitcoind.validate_address(&address.to_string()).unwrap();
+        assert!(info.is_valid);
+        assert_eq!(info.is_mine, None);
+    }
+
+    #[test]
+    fn test_decode_raw_transaction() {
+        use bitcoincore_rpc::bitcoin::{consensus, Amount};
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_btc(1.0).unwrap())
+            .unwrap();
+        let raw_tx = consensus::serialize(&bitcoind.get_tx(&txid).unwrap());
+        let decoded = bitcoind.decode_raw_transaction(&raw_tx).unwrap();
+        assert_eq!(decoded["txid"], txid.to_string());
+        assert!(bitcoind.decode_raw_transaction(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_decode_script() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, Some(bitcoincore_rpc::json::AddressType::Legacy))
+            .unwrap()
+            .assume_checked();
+        let info = bitcoind.decode_script(&address.script_pubkey()).unwrap();
+        assert_eq!(info.type_, "pubkeyhash");
+        assert!(info.asm.starts_with("OP_DUP OP_HASH160"));
+        assert_eq!(info.addresses, vec![address.to_string()]);
+    }
+
+    #[test]
+    fn test_txoutsetinfo() {
+        use bitcoincore_rpc::bitcoin::Amount;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let hashes = bitcoind.client.generate_to_address(3, &address).unwrap();
+        let info = bitcoind.txoutsetinfo().unwrap();
+        assert_eq!(info.height, 3);
+        assert_eq!(info.best_block, hashes[2]);
+        assert_eq!(info.transactions, 3);
+        assert_eq!(info.total_amount, Amount::from_btc(150.0).unwrap());
+        assert_eq!(info, bitcoind.txoutsetinfo().unwrap());
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(10, &address).unwrap();
+        assert!(bitcoind.verify_chain(4, 0).unwrap());
+        assert!(bitcoind.verify_chain(1, 5).unwrap());
+        assert!(bitcoind.verify_chain(5, 0).is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_with_conf_async() {
+        let exe = init();
+        let mut bitcoind = BitcoinD::with_conf_async(exe, &Conf::default())
+            .await
+            .unwrap();
+        assert_eq!(0, bitcoind.client.get_blockchain_info().unwrap().blocks);
+        assert!(bitcoind.stop_async().await.unwrap().success());
+    }
+
+    #[test]
+    fn test_for_lightning() {
+        let _ = init();
+        let bitcoind = BitcoinD::for_lightning().unwrap();
+        assert_eq!(101, bitcoind.client.get_block_count().unwrap());
+        assert!(bitcoind.params.zmq_pub_raw_block_socket.is_some());
+        assert!(bitcoind.params.zmq_pub_raw_tx_socket.is_some());
+        assert!(bitcoind.client.get_balance(None, None).unwrap().to_sat() > 0);
+    }
+
+    #[test]
+    fn test_accept_non_std_txns() {
+        let exe = init();
+        // version 4 transactions are non-standard
+        let amount = Amount::from_sat(100_000);
+        assert!(!tx_accepted(&exe, &Conf::default(), 4, amount));
+        let conf = Conf {
+            accept_non_std_txns: Some(true),
+            ..Conf::default()
+        };
+        assert!(tx_accepted(&exe, &conf, 4, amount));
+
+        // with the default an arg in `Conf::args` is honored
+        let mut conf = Conf::default();
+        conf.args.push("-acceptnonstdtxn=1");
+        assert!(tx_accepted(&exe, &conf, 4, amount));
+
+        let conf = Conf {
+            accept_non_std_txns: Some(true),
+            ..conf
+        };
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-acceptnonstdtxn"))
+        ));
+    }
+
+    #[test]
+    fn test_dust_relay_fee() {
+        let exe = init();
+        let conf = Conf {
+            dust_relay_fee: Some(0.0),
+            ..Conf::default()
+        };
+        // with zero dust relay fee even a 1 sat output isn't dust
+        assert!(tx_accepted(&exe, &conf, 2, Amount::from_sat(1)));
+
+        let conf = Conf {
+            dust_relay_fee: Some(11.0),
+            ..Conf::default()
+        };
+        assert!(BitcoinD::with_conf(&exe, &conf).is_err());
+
+        let mut conf = Conf {
+            dust_relay_fee: Some(1.0),
+            ..Conf::default()
+        };
+        conf.args.push("-dustrelayfee=0.00001");
+        assert!(BitcoinD::with_conf(&exe, &conf).is_err());
+    }
+
+    /// Whether a transaction with the given `version` paying `amount` is accepted in the mempool
+    /// of a node launched with `conf`
+    fn tx_accepted(exe: &str, conf: &Conf, version: i32, amount: Amount) -> bool {
+        let bitcoind = BitcoinD::with_conf(exe, conf).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let mut outputs = std::collections::HashMap::new();
+        outputs.insert(address.to_string(), amount);
+        let tx = bitcoind
+            .client
+            .create_raw_transaction(&[], &outputs, None, None)
+            .unwrap();
+        let mut tx = bitcoind
+            .client
+            .fund_raw_transaction(&tx, None, None)
+            .unwrap()
+            .transaction()
+            .unwrap();
+        tx.version = transaction::Version(version);
+        let tx = bitcoind
+            .client
+            .sign_raw_transaction_with_wallet(&tx, None, None)
+            .unwrap()
+            .transaction()
+            .unwrap();
+        bitcoind.client.test_mempool_accept(&[&tx]).unwrap()[0].allowed
+    }
+
+    #[test]
+    fn test_min_relay_tx_fee() {
+        let exe = init();
+        let conf = Conf {
+            min_relay_tx_fee: Some(0.00002),
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let info: Value = bitcoind.client.call("getnetworkinfo", &[]).unwrap();
+        assert_eq!(info["relayfee"].as_f64(), Some(0.00002));
+
+        let mut conf = Conf {
+            min_relay_tx_fee: Some(0.00002),
+            ..Conf::default()
+        };
+        conf.args.push("-minrelaytxfee=0.00003");
+        assert!(BitcoinD::with_conf(&exe, &conf).is_err());
+    }
+
+    #[test]
+    fn test_mempool_size_mb() {
+        let exe = init();
+        let conf = Conf {
+            mempool_size_mb: Some(5),
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        // every transaction spends a different mature coinbase
+        bitcoind.client.generate_to_address(201, &address).unwrap();
+
+        let mut outputs = Map::new();
+        for _ in 0..1000 {
+            let address = bitcoind.client.get_new_address(None, None).unwrap();
+            outputs.insert(address.assume_checked().to_string(), 0.0001.into());
+        }
+        let outputs = Value::Object(outputs);
+
+        let relay_fee = |bitcoind: &BitcoinD| {
+            let info: Value = bitcoind.client.call("getnetworkinfo", &[]).unwrap();
+            info["relayfee"].as_f64().unwrap()
+        };
+        let mempool_min_fee = |bitcoind: &BitcoinD| {
+            let info: Value = bitcoind.client.call("getmempoolinfo", &[]).unwrap();
+            info["mempoolminfee"].as_f64().unwrap()
+        };
+
+        assert_eq!(mempool_min_fee(&bitcoind), relay_fee(&bitcoind));
+        for _ in 0..100 {
+            // all the transactions pay the same fee rate, when the mempool is full the new one
+            // may be evicted right away and the wallet returns an error
+            let sent = bitcoind
+                .client
+                .call::<Value>("sendmany", &["".into(), outputs.clone()]);
+            if sent.is_err() || mempool_min_fee(&bitcoind) > relay_fee(&bitcoind) {
+                break;
+            }
+        }
+        assert!(mempool_min_fee(&bitcoind) > relay_fee(&bitcoind));
+
+        let mut conf = conf.clone();
+        conf.args.push("-maxmempool=10");
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-maxmempool"))
+        ));
+    }
+
+    #[test]
+    fn test_network_info() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let info = bitcoind.network_info().unwrap();
+        assert!(info.version >= 170_000);
+        assert!(info.subversion.starts_with("/Satoshi:"));
+        assert_eq!(info.connections, 0);
+        assert!(info.network_active);
+        assert_eq!(info.relay_fee, 0.00001);
+    }
+
+    #[test]
+    fn test_wallet_info() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let info = bitcoind.wallet_info().unwrap();
+        assert_eq!(info.wallet_name, "default");
+        assert_eq!(info.balance, Amount::ZERO);
+        assert!(info.scanning.is_none());
+
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let info = bitcoind.wallet_info().unwrap();
+        assert_eq!(info.balance, Amount::from_btc(50.0).unwrap());
+        assert_eq!(info.immature_balance, Amount::from_btc(5000.0).unwrap());
+        assert_eq!(info.unconfirmed_balance, Amount::ZERO);
+    }
+
+    #[test]
+    fn test_mining_info() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let info = bitcoind.mining_info().unwrap();
+        assert_eq!(info.blocks, 0);
+        assert_eq!(info.chain, "regtest");
+        assert!(info.difficulty > 0.0);
+
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(10, &address).unwrap();
+        let info = bitcoind.mining_info().unwrap();
+        assert_eq!(info.blocks, 10);
+        assert!(info.network_hash_ps > 0.0);
+    }
+
+    #[test]
+    fn test_connect_params_display() {
+        let mut params = ConnectParams {
+            cookie_file: PathBuf::from("/tmp/.cookie"),
+            rpc_socket: SocketAddrV4::new(LOCAL_IP, 18443),
+            p2p_socket: None,
+            zmq_pub_raw_block_socket: None,
+            zmq_pub_raw_tx_socket: None,
+            network: "regtest".to_string(),
+        };
+        assert_eq!(params.to_string(), "rpc=127.0.0.1:18443");
+
+        params.p2p_socket = Some(SocketAddrV4::new(LOCAL_IP, 18444));
+        params.zmq_pub_raw_block_socket = Some(SocketAddrV4::new(LOCAL_IP, 18445));
+        params.zmq_pub_raw_tx_socket = Some(SocketAddrV4::new(LOCAL_IP, 18446));
+        assert_eq!(
+            params.to_string(),
+            "rpc=127.0.0.1:18443 p2p=127.0.0.1:18444 zmq_block=127.0.0.1:18445 zmq_tx=127.0.0.1:18446"
+        );
+    }
+
+    #[test]
+    fn test_to_bitcoin_network() {
+        let mut params = ConnectParams {
+            cookie_file: PathBuf::from("/tmp/.cookie"),
+            rpc_socket: SocketAddrV4::new(LOCAL_IP, 18443),
+            p2p_socket: None,
+            zmq_pub_raw_block_socket: None,
+            zmq_pub_raw_tx_socket: None,
+            network: "regtest".to_string(),
+        };
+        assert_eq!(params.to_bitcoin_network(), Some(Network::Regtest));
+        for (name, network) in [
+            ("main", Network::Bitcoin),
+            ("", Network::Bitcoin),
+            ("testnet", Network::Testnet),
+            ("signet", Network::Signet),
+        ] {
+            params.network = name.to_string();
+            assert_eq!(params.to_bitcoin_network(), Some(network));
+        }
+        params.network = "esoteric".to_string();
+        assert_eq!(params.to_bitcoin_network(), None);
+    }
+
+    #[test]
+    fn test_wait_for_cookie() {
+        let dir = TempDir::new().unwrap();
+        let params = ConnectParams {
+            cookie_file: dir.path().join(".cookie"),
+            rpc_socket: SocketAddrV4::new(LOCAL_IP, 18443),
+            p2p_socket: None,
+            zmq_pub_raw_block_socket: None,
+            zmq_pub_raw_tx_socket: None,
+            network: "regtest".to_string(),
+        };
+        assert!(params.wait_for_cookie(Duration::from_millis(200)).is_err());
+
+        std::fs::write(&params.cookie_file, "__cookie__:password").unwrap();
+        params.wait_for_cookie(Duration::from_millis(200)).unwrap();
+    }
+
+    #[test]
+    fn test_data_dir_display() {
+        let data_dir = DataDir::Persistent(PathBuf::from("/tmp/bitcoind"));
+        assert_eq!(data_dir.to_string(), "/tmp/bitcoind");
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().display().to_string();
+        assert_eq!(DataDir::Temporary(temp_dir).to_string(), path);
+    }
+
+    #[test]
+    fn test_work_dir_size() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let size = bitcoind.work_dir_size().unwrap();
+        assert!(size > 0);
+
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        bitcoind.client.generate_to_address(100, &address).unwrap();
+        assert!(bitcoind.work_dir_size().unwrap() > size);
+    }
+
+    #[test]
+    fn test_debug_log_path() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let path = bitcoind.debug_log_path();
+        assert!(path.ends_with("regtest/debug.log"));
+        let log = std::fs::read_to_string(path).unwrap();
+        assert!(log.contains("Bitcoin"));
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("debug.log");
+        let content: String = (0..2000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, content).unwrap();
+
+        assert_eq!(
+            tail_lines(&path, 3).unwrap(),
+            vec!["line 1997", "line 1998", "line 1999"]
+        );
+        assert!(tail_lines(&path, 0).unwrap().is_empty());
+        let all = tail_lines(&path, 5000).unwrap();
+        assert_eq!(all.len(), 2000);
+        assert_eq!(all[0], "line 0");
+    }
+
+    #[test]
+    fn test_tail_debug_log() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let lines = bitcoind.tail_debug_log(5).unwrap();
+        assert_eq!(lines.len(), 5);
+        let log = std::fs::read_to_string(bitcoind.debug_log_path()).unwrap();
+        assert!(log.contains(&lines[0]));
+    }
+
+    #[test]
+    fn test_error_from_anyhow() {
+        fn fails() -> Result<(), Error> {
+            let _ = TempDir::new_in("/not/existing/dir").context("cannot create tempdir")?;
+            Ok(())
+        }
+        let err = fails().unwrap_err();
+        assert!(matches!(err, Error::Other(_)));
+        assert_eq!(err.to_string(), "cannot create tempdir");
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_error_into_anyhow() {
+        fn fails() -> anyhow::Result<()> {
+            let _ = crate::validate_args(vec!["-rpcuser=user"])?;
+            Ok(())
+        }
+        let err = fails().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::RpcUserAndPasswordUsed)
+        ));
+    }
+
+    #[test]
+    fn test_port_unavailable() {
+        assert!(get_available_port().unwrap() > 0);
+        assert_eq!(
+            Error::PortUnavailable(0).to_string(),
+            "cannot book any local port"
+        );
+        assert_eq!(
+            Error::PortUnavailable(18443).to_string(),
+            "local port 18443 is unavailable"
+        );
+    }
+
+    #[test]
+    fn test_process_spawn_failed() {
+        let err = BitcoinD::new("/not/existing/bitcoind").unwrap_err();
+        match err {
+            Error::ProcessSpawnFailed(exe, e) => {
+                assert_eq!(exe, "/not/existing/bitcoind");
+                assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[cfg(any(feature = "0_19_1", not(feature = "download")))]
+    #[test]
+    fn test_wallet_error() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        bitcoind.create_wallet("alice").unwrap();
+        match bitcoind.create_wallet("alice").unwrap_err() {
+            Error::WalletError { wallet, .. } => assert_eq!(wallet, "alice"),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_supported_versions() {
+        use crate::versions::SUPPORTED_VERSIONS;
+        let parse = |v: &str| -> Vec<u32> { v.split('.').map(|n| n.parse().unwrap()).collect() };
+        let parsed: Vec<_> = SUPPORTED_VERSIONS.iter().map(|v| parse(v)).collect();
+        let mut sorted = parsed.clone();
+        sorted.sort();
+        assert_eq!(parsed, sorted);
+        #[cfg(feature = "download")]
+        assert!(SUPPORTED_VERSIONS.contains(&crate::versions::VERSION));
+    }
+
+    #[test]
+    fn test_version_from_feature() {
+        assert_eq!(version_from_feature("0_17_1"), Some("0.17.1"));
+        assert_eq!(version_from_feature("26_0"), Some("26.0"));
+        assert_eq!(version_from_feature("24_0_1"), Some("24.0.1"));
+        assert_eq!(version_from_feature("0_16_0"), None);
+        assert_eq!(version_from_feature("download"), None);
+        assert_eq!(version_from_feature("26.0"), None);
+    }
+
+    #[cfg(feature = "all_versions")]
+    #[test]
+    fn test_from_version() {
+        use crate::versions::SUPPORTED_VERSIONS;
+        for version in SUPPORTED_VERSIONS {
+            let bitcoind = BitcoinD::from_version(version).unwrap();
+            assert!(bitcoind.client.get_network_info().unwrap().version > 0);
+        }
+        assert!(matches!(
+            BitcoinD::from_version("0.16.0"),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_is_method_not_found() {
+        use crate::bitcoincore_rpc::jsonrpc::error::{Error as JsonRpcError, RpcError};
+        let rpc_error = |code| {
+            crate::bitcoincore_rpc::Error::JsonRpc(JsonRpcError::Rpc(RpcError {
+                code,
+                message: "error".to_string(),
+                data: None,
+            }))
+        };
+        assert!(crate::is_method_not_found(&rpc_error(-32601)));
+        assert!(!crate::is_method_not_found(&rpc_error(-18)));
+        assert!(crate::is_rpc_error(
+            &rpc_error(-5),
+            crate::RPC_INVALID_ADDRESS_OR_KEY
+        ));
+        let other = crate::bitcoincore_rpc::Error::ReturnedError("error".to_string());
+        assert!(!crate::is_method_not_found(&other));
+    }
+
+    #[test]
+    fn test_exe_in_dir() {
+        let exe = exe_in_dir("/opt/bitcoin/bin");
+        if cfg!(target_os = "windows") {
+            assert_eq!(exe, PathBuf::from("/opt/bitcoin/bin/bitcoind.exe"));
+        } else {
+            assert_eq!(exe, PathBuf::from("/opt/bitcoin/bin/bitcoind"));
+        }
+    }
+
+    #[cfg(not(feature = "download"))]
+    #[test]
+    fn test_downloaded_exe_path_no_feature() {
+        assert!(matches!(
+            crate::downloaded_exe_path(),
+            Err(Error::NoFeature)
+        ));
+        let fallback = crate::downloaded_exe_path().or_else(|_| Ok::<_, Error>("bitcoind".into()));
+        assert_eq!(fallback.unwrap(), "bitcoind");
+    }
+
+    #[test]
+    fn test_cookie_path() {
+        let exe = init();
+        let cookie_dir = TempDir::new().unwrap();
+        let cookie_path = cookie_dir.path().join("regtest.cookie");
+        let conf = Conf {
+            cookie_path: Some(cookie_path.clone()),
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert_eq!(bitcoind.params.cookie_file, cookie_path);
+        assert!(cookie_path.exists());
+        assert_eq!(0, bitcoind.client.get_blockchain_info().unwrap().blocks);
+
+        let mut conf = conf.clone();
+        conf.args.push("-rpccookiefile=/tmp/other.cookie");
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-rpccookiefile"))
+        ));
+    }
+
+    #[test]
+    fn test_get_receive_address() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        assert_eq!(bitcoind.network, "regtest");
+        let address = bitcoind.get_receive_address().unwrap();
+        assert!(address
+            .as_unchecked()
+            .is_valid_for_network(Network::Regtest));
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+        assert_eq!(1, bitcoind.client.get_block_count().unwrap());
+    }
+
+    #[test]
+    fn test_client_clone() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let client = bitcoind.client_clone().unwrap();
+        let address = client.get_new_address(None, None).unwrap().assume_checked();
+        std::thread::spawn(move || client.generate_to_address(1, &address).unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(1, bitcoind.client.get_block_count().unwrap());
+    }
+
+    #[test]
+    fn test_try_stop() {
+        let exe = init();
+        let mut bitcoind = BitcoinD::new(&exe).unwrap();
+        assert!(bitcoind.try_stop().unwrap().is_none());
+
+        let mut bitcoind = BitcoinD::new(&exe).unwrap();
+        bitcoind.process.kill().unwrap();
+        bitcoind.process.wait().unwrap();
+        let status = bitcoind.try_stop().unwrap().unwrap();
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn test_no_wallet() {
+        let exe = init();
+        let conf = Conf {
+            no_wallet: true,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(exe, &conf).unwrap();
+        assert_eq!(0, bitcoind.client.get_blockchain_info().unwrap().blocks);
+        assert!(bitcoind.client.list_wallets().unwrap().is_empty());
+        #[cfg(any(feature = "0_19_1", not(feature = "download")))]
+        assert!(matches!(
+            bitcoind.create_wallet("alice"),
+            Err(Error::NoWallet)
+        ));
+    }
+
+    #[test]
+    fn test_assume_valid() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(&exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        let hashes = bitcoind.client.generate_to_address(1, &address).unwrap();
+
+        let conf = Conf {
+            assume_valid: Some(hashes[0]),
+            ..Conf::default()
+        };
+        let other = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert_eq!(0, other.client.get_block_count().unwrap());
+
+        let conf = Conf {
+            assume_valid: Some(BlockHash::all_zeros()),
+            ..Conf::default()
+        };
+        BitcoinD::with_conf(&exe, &conf).unwrap();
+
+        let mut conf = conf.clone();
+        conf.args.push("-assumevalid=0");
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-assumevalid"))
+        ));
+    }
+
+    #[test]
+    fn test_min_chain_work() {
+        let exe = init();
+        let conf = Conf {
+            min_chain_work: Some("0x00".to_string()),
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert_eq!(0, bitcoind.client.get_block_count().unwrap());
+
+        let mut conf = conf.clone();
+        conf.args.push("-minimumchainwork=0x00");
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-minimumchainwork"))
+        ));
+    }
+
+    #[test]
+    fn test_blocksonly() {
+        let exe = init();
+        let conf = Conf {
+            blocksonly: true,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert!(!bitcoind.client.get_network_info().unwrap().local_relay);
+
+        let mut conf = conf.clone();
+        conf.args.push("-blocksonly=1");
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-blocksonly"))
+        ));
+    }
+
+    #[test]
+    fn test_reindex() {
+        let exe = init();
+        let datadir = TempDir::new().unwrap();
+        let conf = Conf {
+            staticdir: Some(datadir.path().to_path_buf()),
+            ..Conf::default()
+        };
+        let mut bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(5, &address).unwrap();
+        bitcoind.stop().unwrap();
+
+        let conf = Conf {
+            reindex: true,
+            ..conf
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(30);
+        while bitcoind.client.get_block_count().unwrap() < 5 {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "reindex not completed"
+            );
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        let mut conf = conf.clone();
+        conf.args.push("-reindex=1");
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::ConflictingArg("-reindex"))
+        ));
+    }
+
+    #[test]
+    fn test_addnode() {
+        let exe = init();
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let conf = Conf {
+            addnode: vec![bitcoind.params.p2p_socket.unwrap()],
+            ..Conf::default()
+        };
+        let other_bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert!(other_bitcoind.params.p2p_socket.is_some());
+        assert!(wait_for_peers_connected(&bitcoind.client, 1));
+        assert!(wait_for_peers_connected(&other_bitcoind.client, 1));
+    }
+
+    #[test]
+    fn test_node_address() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(&exe).unwrap();
+        assert!(bitcoind.node_address().is_none());
+
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let port = bitcoind.params.p2p_socket.unwrap().port();
+        assert_eq!(
+            bitcoind.node_address().unwrap(),
+            format!("127.0.0.1:{}", port)
+        );
+    }
+
+    #[test]
+    fn test_is_txindex_enabled() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(&exe).unwrap();
+        assert!(!bitcoind.is_txindex_enabled().unwrap());
+
+        let mut conf = Conf::default();
+        conf.args.push("-txindex");
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert!(bitcoind.is_txindex_enabled().unwrap());
+    }
+
+    #[test]
+    fn test_network_active() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        assert!(bitcoind.network_active().unwrap());
+        bitcoind.disconnect_network().unwrap();
+        assert!(!bitcoind.network_active().unwrap());
+        bitcoind.reconnect_network().unwrap();
+        assert!(bitcoind.network_active().unwrap());
+    }
+
+    #[cfg(any(feature = "0_18_1", not(feature = "download")))]
+    #[test]
+    fn test_rpc_info() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        assert!(bitcoind.rpc_info().unwrap().active_calls.is_empty());
+
+        let client = bitcoind.client_clone().unwrap();
+        let handle = std::thread::spawn(move || {
+            // waits for the whole timeout since no block is mined
+            client.call::<Value>("waitfornewblock", &[2000.into()])
+        });
+        let deadline = std::time::Instant::now() + Duration::from_secs(1);
+        let mut calls = vec![];
+        while calls.is_empty() && std::time::Instant::now() < deadline {
+            calls = bitcoind.rpc_info().unwrap().active_calls;
+        }
+        assert_eq!(calls, vec!["waitfornewblock".to_string()]);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[cfg(any(feature = "0_18_1", not(feature = "download")))]
+    #[test]
+    fn test_get_block_stats() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+
+        let stats = bitcoind.get_block_stats(102).unwrap();
+        assert_eq!(stats.height, 102);
+        assert_eq!(stats.txs, 2);
+        assert!(stats.total_size > 0);
+        assert!(stats.min_fee_rate > 0);
+        assert!(stats.max_fee_rate >= stats.avg_fee_rate);
+
+        let stats = bitcoind.get_block_stats(1).unwrap();
+        assert_eq!((stats.txs, stats.avg_fee_rate), (1, 0));
+    }
+
+    #[test]
+    fn test_generate_blocks_with_interval() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let hashes = bitcoind.generate_blocks_with_interval(3, 600).unwrap();
+        assert_eq!(hashes.len(), 3);
+        let times: Vec<_> = hashes
+            .iter()
+            .map(|h| bitcoind.client.get_block_header(h).unwrap().time)
+            .collect();
+        assert_eq!(times[1] - times[0], 600);
+        assert_eq!(times[2] - times[1], 600);
+    }
+
+    #[test]
+    fn test_simulate_reorg() {
+        let exe = init();
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let attacker = BitcoinD::with_conf(&exe, &conf).unwrap();
+        let victim_conf = Conf {
+            p2p: attacker.p2p_connect(false).unwrap(),
+            ..Conf::default()
+        };
+        let victim = BitcoinD::with_conf(&exe, &victim_conf).unwrap();
+        assert!(wait_for_peers_connected(&victim.client, 1));
+
+        let address = attacker.get_receive_address().unwrap();
+        attacker.client.generate_to_address(10, &address).unwrap();
+        let attacker_tip = attacker.client.get_best_block_hash().unwrap();
+        while victim.client.get_best_block_hash().unwrap() != attacker_tip {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+
+        attacker.simulate_reorg(&victim, 2, 1).unwrap();
+        assert_eq!(victim.client.get_block_count().unwrap(), 13);
+        let tips = victim.get_chain_tips().unwrap();
+        assert!(tips.iter().any(|t| t.height == 12 && t.branch_length == 2));
+    }
+
+    #[test]
+    fn test_create_multisig() {
+        use crate::bitcoincore_rpc::bitcoin::secp256k1::{Secp256k1, SecretKey};
+        use crate::bitcoincore_rpc::bitcoin::PublicKey;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let secp = Secp256k1::new();
+        let keys: Vec<_> = (1..=3u8)
+            .map(|i| {
+                let sk = SecretKey::from_slice(&[i; 32]).unwrap();
+                PublicKey::new(sk.public_key(&secp))
+            })
+            .collect();
+        let multisig = bitcoind.create_multisig(2, &keys).unwrap();
+        let info = bitcoind.decode_script(&multisig.redeem_script).unwrap();
+        assert_eq!(info.type_, "multisig");
+        assert!(info.asm.starts_with("2 "));
+        assert!(multisig
+            .address
+            .as_unchecked()
+            .is_valid_for_network(Network::Regtest));
+    }
+
+    #[test]
+    fn test_sign_psbt() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+
+        let mut outputs = Map::new();
+        outputs.insert(address.to_string(), Value::from(1.0));
+        let created: Value = bitcoind
+            .client
+            .call(
+                "walletcreatefundedpsbt",
+                &[Value::Array(vec![]), Value::Object(outputs)],
+            )
+            .unwrap();
+        let bytes = base64::decode(created["psbt"].as_str().unwrap()).unwrap();
+        let psbt = Psbt::deserialize(&bytes).unwrap();
+
+        let (signed, complete) = bitcoind.sign_psbt(&bitcoind.client, &psbt).unwrap();
+        assert!(complete);
+        assert!(signed
+            .inputs
+            .iter()
+            .all(|i| i.final_script_witness.is_some() || i.final_script_sig.is_some()));
+
+        assert!(bitcoind.finalize_psbt(&psbt).unwrap().is_none());
+        let tx = bitcoind.finalize_psbt(&signed).unwrap().unwrap();
+        let txid = bitcoind.client.send_raw_transaction(&tx).unwrap();
+        assert_eq!(txid, tx.compute_txid());
+    }
+
+    #[test]
+    fn test_create_psbt() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let utxo = &bitcoind
+            .client
+            .list_unspent(None, None, None, None, None)
+            .unwrap()[0];
+
+        let mut outputs = std::collections::HashMap::new();
+        outputs.insert(
+            address.clone(),
+            Amount::from_sat(utxo.amount.to_sat() - 10_000),
+        );
+        let psbt = bitcoind
+            .create_psbt(&[(utxo.txid, utxo.vout)], &outputs)
+            .unwrap();
+        assert_eq!(psbt.unsigned_tx.input[0].previous_output.txid, utxo.txid);
+        assert_eq!(
+            psbt.unsigned_tx.output[0].script_pubkey,
+            address.script_pubkey()
+        );
+
+        let (signed, complete) = bitcoind.sign_psbt(&bitcoind.client, &psbt).unwrap();
+        assert!(complete);
+        assert!(bitcoind.finalize_psbt(&signed).unwrap().is_some());
+    }
+
+    #[cfg(any(feature = "0_21_2", not(feature = "download")))]
+    #[test]
+    fn test_bump_fee() {
+        use crate::SendToAddressOptions;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+
+        let opts = SendToAddressOptions {
+            replaceable: Some(true),
+            ..Default::default()
+        };
+        let txid = bitcoind
+            .send_to_address_with_opts(&address, Amount::from_sat(100_000), &opts)
+            .unwrap();
+        let original = bitcoind.get_tx(&txid).unwrap();
+
+        let new_txid = bitcoind.bump_fee(&bitcoind.client, &txid, 50.0).unwrap();
+        assert_ne!(txid, new_txid);
+        let mempool = bitcoind.client.get_raw_mempool().unwrap();
+        assert!(mempool.contains(&new_txid));
+        assert!(!mempool.contains(&txid));
 
-    Ok(args)
-}
+        let accept = bitcoind.client.test_mempool_accept(&[&original]).unwrap();
+        assert!(!accept[0].allowed);
+    }
 
-#[cfg(test)]
-mod test {
-    use crate::bitcoincore_rpc::jsonrpc::serde_json::Value;
-    use crate::bitcoincore_rpc::{Auth, Client};
-    use crate::exe_path;
-    use crate::{get_available_port, BitcoinD, Conf, LOCAL_IP, P2P};
-    use bitcoincore_rpc::RpcApi;
-    use std::net::SocketAddrV4;
-    use tempfile::TempDir;
+    #[test]
+    fn test_get_fee_rate() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(&exe).unwrap();
+        // default conf has `-fallbackfee=0.0001`, 10 sat/vB
+        assert_eq!(bitcoind.get_fee_rate(6).unwrap().to_sat_per_vb_floor(), 10);
+
+        let conf = Conf {
+            args: vec!["-regtest"],
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert_eq!(bitcoind.get_fee_rate(6).unwrap().to_sat_per_vb_floor(), 1);
+    }
 
     #[test]
-    fn test_local_ip() {
-        assert_eq!("127.0.0.1", format!("{}", LOCAL_IP));
-        let port = get_available_port().unwrap();
-        let socket = SocketAddrV4::new(LOCAL_IP, port);
-        assert_eq!(format!("127.0.0.1:{}", port), format!("{}", socket));
+    fn test_raw_mempool() {
+        use crate::{MempoolQuery, RawMempool};
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let parent = bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
+        let child = bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
+
+        let mut txids = match bitcoind.raw_mempool(MempoolQuery::Txids).unwrap() {
+            RawMempool::Txids(txids) => txids,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(txids.len(), 2);
+
+        let mempool = bitcoind.raw_mempool(MempoolQuery::Entries).unwrap();
+        let mut entry_txids = mempool.txids();
+        entry_txids.sort();
+        txids.sort();
+        assert_eq!(entry_txids, txids);
+        let entries = match mempool {
+            RawMempool::Entries(entries) => entries,
+            other => panic!("unexpected {:?}", other),
+        };
+        let parent_entry = &entries[&parent];
+        assert!(parent_entry.fee > Amount::ZERO);
+        assert!(parent_entry.weight >= parent_entry.vsize * 3);
+        assert!(parent_entry.fee_rate > 0.0);
+        let child_entry = &entries[&child];
+        assert_eq!(child_entry.depends, vec![parent]);
     }
 
     #[test]
-    fn test_bitcoind() {
+    fn test_ancestor_and_descendant_count() {
         let exe = init();
         let bitcoind = BitcoinD::new(exe).unwrap();
-        let info = bitcoind.client.get_blockchain_info().unwrap();
-        assert_eq!(0, info.blocks);
-        let address = bitcoind
-            .client
-            .get_new_address(None, None)
-            .unwrap()
-            .assume_checked();
-        let _ = bitcoind.client.generate_to_address(1, &address).unwrap();
-        let info = bitcoind.client.get_blockchain_info().unwrap();
-        assert_eq!(1, info.blocks);
+        let address = bitcoind.get_receive_address().unwrap();
+        // only the first coinbase is mature, so every send spends the change of the previous
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let chain: Vec<_> = (0..5)
+            .map(|_| {
+                bitcoind
+                    .send_to_address(&address, Amount::from_sat(100_000))
+                    .unwrap()
+            })
+            .collect();
+
+        for (i, txid) in chain.iter().enumerate() {
+            assert_eq!(bitcoind.ancestor_count(txid).unwrap(), i as u32 + 1);
+            assert_eq!(bitcoind.descendant_count(txid).unwrap(), 5 - i as u32);
+        }
     }
 
+    #[cfg(any(feature = "0_21_2", not(feature = "download")))]
     #[test]
-    #[cfg(feature = "0_21_2")]
-    fn test_getindexinfo() {
+    fn test_unbroadcast_count() {
         let exe = init();
-        let mut conf = Conf::default();
-        conf.args.push("-txindex");
-        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
-        assert!(
-            bitcoind.client.version().unwrap() >= 210_000,
-            "getindexinfo requires bitcoin >0.21"
-        );
-        let info: std::collections::HashMap<String, bitcoincore_rpc::jsonrpc::serde_json::Value> =
-            bitcoind.client.call("getindexinfo", &[]).unwrap();
-        assert!(info.contains_key("txindex"));
-        assert!(bitcoind.client.version().unwrap() >= 210_000);
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        assert_eq!(bitcoind.unbroadcast_count().unwrap(), 0);
+
+        // without peers the transaction is never received by anyone
+        bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
+        assert_eq!(bitcoind.unbroadcast_count().unwrap(), 1);
     }
 
+    #[cfg(any(feature = "24_0_1", not(feature = "download")))]
     #[test]
-    fn test_p2p() {
+    fn test_get_tx_spending_prevout() {
         let exe = init();
-        let mut conf = Conf::default();
-        conf.p2p = P2P::Yes;
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let parent = bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
+        let child = bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
 
-        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
-        assert_eq!(peers_connected(&bitcoind.client), 0);
-        let mut other_conf = Conf::default();
-        other_conf.p2p = bitcoind.p2p_connect(false).unwrap();
+        let spent = bitcoind.get_tx(&child).unwrap().input[0].previous_output;
+        assert_eq!(spent.txid, parent);
+        assert_eq!(
+            bitcoind.get_tx_spending_prevout(&spent).unwrap(),
+            Some(child)
+        );
 
-        let other_bitcoind = BitcoinD::with_conf(&exe, &other_conf).unwrap();
-        assert_eq!(peers_connected(&bitcoind.client), 1);
-        assert_eq!(peers_connected(&other_bitcoind.client), 1);
+        let unspent = crate::bitcoincore_rpc::bitcoin::OutPoint::new(child, 0);
+        assert_eq!(bitcoind.get_tx_spending_prevout(&unspent).unwrap(), None);
     }
 
-    #[cfg(not(target_os = "windows"))] // TODO: investigate why it doesn't work in windows
     #[test]
-    fn test_data_persistence() {
-        // Create a Conf with staticdir type
+    fn test_wait_for_tx_confirmation() {
+        let exe = init();
         let mut conf = Conf::default();
-        let datadir = TempDir::new().unwrap();
-        conf.staticdir = Some(datadir.path().to_path_buf());
+        conf.args.push("-txindex");
+        let mut bitcoind = BitcoinD::with_conf(exe, &conf).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
 
-        // Start BitcoinD with persistent db config
-        // Generate 101 blocks
-        // Wallet balance should be 50
-        let bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
-        let core_addrs = bitcoind
-            .client
-            .get_new_address(None, None)
-            .unwrap()
-            .assume_checked();
+        let timeout = std::time::Duration::from_millis(300);
+        let err = bitcoind
+            .wait_for_tx_confirmation(&txid, 1, timeout)
+            .unwrap_err();
+        assert!(err.to_string().contains(&txid.to_string()));
+
+        bitcoind.client.generate_to_address(2, &address).unwrap();
         bitcoind
-            .client
-            .generate_to_address(101, &core_addrs)
+            .wait_for_tx_confirmation(&txid, 2, timeout)
             .unwrap();
-        let wallet_balance_1 = bitcoind.client.get_balance(None, None).unwrap();
-        let best_block_1 = bitcoind.client.get_best_block_hash().unwrap();
 
-        drop(bitcoind);
+        // errors other than an unknown transaction aren't waited on
+        bitcoind.stop().unwrap();
+        let start = std::time::Instant::now();
+        let timeout = std::time::Duration::from_secs(10);
+        assert!(bitcoind
+            .wait_for_tx_confirmation(&txid, 3, timeout)
+            .is_err());
+        assert!(start.elapsed() < timeout);
+    }
 
-        // Start a new BitcoinD with the same datadir
-        let bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+    #[test]
+    fn test_broadcast_and_mine() {
+        use bitcoincore_rpc::bitcoin::consensus;
+        let exe = init();
+        let node = BitcoinD::new(&exe).unwrap();
+        let other = BitcoinD::new(&exe).unwrap();
+        let address = node.get_receive_address().unwrap();
+        node.client.generate_to_address(101, &address).unwrap();
+        let utxo = &node
+            .client
+            .list_unspent(None, None, None, None, None)
+            .unwrap()[0];
+        let mut outputs = std::collections::HashMap::new();
+        outputs.insert(address, Amount::from_sat(utxo.amount.to_sat() - 10_000));
+        let psbt = node
+            .create_psbt(&[(utxo.txid, utxo.vout)], &outputs)
+            .unwrap();
+        let (signed, _) = node.sign_psbt(&node.client, &psbt).unwrap();
+        let tx = node.finalize_psbt(&signed).unwrap().unwrap();
+        let txid = tx.compute_txid();
+        let raw_tx = consensus::serialize(&tx);
 
-        let wallet_balance_2 = bitcoind.client.get_balance(None, None).unwrap();
-        let best_block_2 = bitcoind.client.get_best_block_hash().unwrap();
+        // the other node doesn't know the spent outputs
+        assert!(other.broadcast_and_mine(&raw_tx).is_err());
 
-        // Check node chain data persists
-        assert_eq!(best_block_1, best_block_2);
+        let (mined_txid, block_hash) = node.broadcast_and_mine(&raw_tx).unwrap();
+        assert_eq!(mined_txid, txid);
+        assert_eq!(node.client.get_best_block_hash().unwrap(), block_hash);
+    }
 
-        // Check the node wallet balance persists
-        assert_eq!(wallet_balance_1, wallet_balance_2);
+    #[test]
+    fn test_mine_block_with_transaction() {
+        use bitcoincore_rpc::bitcoin::consensus;
+        let exe = init();
+        let node = BitcoinD::new(&exe).unwrap();
+        let address = node.get_receive_address().unwrap();
+        node.client.generate_to_address(101, &address).unwrap();
+        let utxo = &node
+            .client
+            .list_unspent(None, None, None, None, None)
+            .unwrap()[0];
+        // without fee the transaction is refused by the mempool
+        let mut outputs = std::collections::HashMap::new();
+        outputs.insert(address, utxo.amount);
+        let psbt = node
+            .create_psbt(&[(utxo.txid, utxo.vout)], &outputs)
+            .unwrap();
+        let (signed, _) = node.sign_psbt(&node.client, &psbt).unwrap();
+        let tx = node.finalize_psbt(&signed).unwrap().unwrap();
+        let raw_tx = consensus::serialize(&tx);
+        assert!(node.client.send_raw_transaction(&raw_tx).is_err());
+
+        let block_hash = node.mine_block_with_transaction(&raw_tx).unwrap();
+        assert_eq!(node.client.get_block_count().unwrap(), 102);
+        let block = node.client.get_block(&block_hash).unwrap();
+        assert_eq!(block.txdata[1].compute_txid(), tx.compute_txid());
     }
 
     #[test]
-    fn test_multi_p2p() {
-        let _ = env_logger::try_init();
-        let mut conf_node1 = Conf::default();
-        conf_node1.p2p = P2P::Yes;
-        let node1 = BitcoinD::with_conf(exe_path().unwrap(), &conf_node1).unwrap();
+    fn test_initial_blocks() {
+        let exe = init();
+        let conf = Conf {
+            initial_blocks: 101,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(&exe, &conf).unwrap();
+        assert_eq!(bitcoind.client.get_block_count().unwrap(), 101);
+        assert_eq!(
+            bitcoind.client.get_balance(None, None).unwrap(),
+            Amount::from_btc(50.0).unwrap()
+        );
 
-        // Create Node 2 connected Node 1
-        let mut conf_node2 = Conf::default();
-        conf_node2.p2p = node1.p2p_connect(true).unwrap();
-        let node2 = BitcoinD::with_conf(exe_path().unwrap(), &conf_node2).unwrap();
+        let conf = Conf {
+            no_wallet: true,
+            ..conf
+        };
+        assert!(matches!(
+            BitcoinD::with_conf(&exe, &conf),
+            Err(Error::NoWallet)
+        ));
+    }
 
-        // Create Node 3 Connected To Node
-        let mut conf_node3 = Conf::default();
-        conf_node3.p2p = node2.p2p_connect(false).unwrap();
-        let node3 = BitcoinD::with_conf(exe_path().unwrap(), &conf_node3).unwrap();
+    #[test]
+    fn test_with_snapshot() {
+        let snapshot_dir = tempfile::TempDir::new().unwrap();
+        let conf = Conf {
+            staticdir: Some(snapshot_dir.path().to_path_buf()),
+            ..Conf::default()
+        };
+        let mut bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(10, &address).unwrap();
+        bitcoind.stop().unwrap();
 
-        // Get each nodes Peers
-        let node1_peers = peers_connected(&node1.client);
-        let node2_peers = peers_connected(&node2.client);
-        let node3_peers = peers_connected(&node3.client);
+        let snapshot = snapshot_dir.path().join("regtest");
+        let bitcoind = BitcoinD::with_snapshot(&snapshot, &Conf::default()).unwrap();
+        assert_eq!(10, bitcoind.client.get_block_count().unwrap());
 
-        // Peers found
-        assert!(node1_peers >= 1);
-        assert!(node2_peers >= 1);
-        assert_eq!(node3_peers, 1, "listen false but more than 1 peer");
+        assert!(BitcoinD::with_snapshot(snapshot_dir.path(), &Conf::default()).is_err());
     }
 
-    #[cfg(any(feature = "0_19_1", not(feature = "download")))]
+    #[cfg(any(feature = "26_0", not(feature = "download")))]
     #[test]
-    fn test_multi_wallet() {
-        use bitcoincore_rpc::bitcoin::Amount;
+    fn test_load_txout_set() {
         let exe = init();
         let bitcoind = BitcoinD::new(exe).unwrap();
-        let alice = bitcoind.create_wallet("alice").unwrap();
-        let alice_address = alice.get_new_address(None, None).unwrap().assume_checked();
-        let bob = bitcoind.create_wallet("bob").unwrap();
-        let bob_address = bob.get_new_address(None, None).unwrap().assume_checked();
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("utxo.dat");
+        assert!(bitcoind
+            .load_txout_set(&missing, Duration::from_secs(1))
+            .is_err());
+    }
+
+    #[cfg(any(feature = "22_1", not(feature = "download")))]
+    #[test]
+    fn test_get_wallet_descriptor() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        // descriptors enabled, they aren't the default before 23.0
         bitcoind
             .client
-            .generate_to_address(1, &alice_address)
+            .call::<Value>(
+                "createwallet",
+                &[
+                    "desc".into(),
+                    false.into(),
+                    false.into(),
+                    "".into(),
+                    false.into(),
+                    true.into(),
+                ],
+            )
             .unwrap();
+        let wallet = Client::new(
+            &bitcoind.rpc_url_with_wallet("desc"),
+            Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+        )
+        .unwrap();
+
+        let descriptors = bitcoind.get_wallet_descriptor(&wallet).unwrap();
+        assert!(!descriptors.external.is_empty());
+        assert_eq!(descriptors.external.len(), descriptors.internal.len());
+        assert!(descriptors.external.iter().any(|d| d.starts_with("wpkh(")));
+        assert!(descriptors.internal.iter().all(|d| d.contains("/1/*)")));
+    }
+
+    #[cfg(any(feature = "0_21_2", not(feature = "download")))]
+    #[test]
+    fn test_import_private_key() {
+        use crate::bitcoincore_rpc::bitcoin::secp256k1::{Secp256k1, SecretKey};
+        use crate::bitcoincore_rpc::bitcoin::{Address, CompressedPublicKey, PrivateKey};
+        let exe = init();
+        let mut conf = Conf::default();
+        conf.args.push("-deprecatedrpc=create_bdb");
+        let bitcoind = BitcoinD::with_conf(exe, &conf).unwrap();
+        // descriptors disabled, the `createwallet` param is available since 0.21
         bitcoind
             .client
-            .generate_to_address(101, &bob_address)
-            .unwrap();
-        assert_eq!(
-            Amount::from_btc(50.0).unwrap(),
-            alice.get_balances().unwrap().mine.trusted
-        );
-        assert_eq!(
-            Amount::from_btc(50.0).unwrap(),
-            bob.get_balances().unwrap().mine.trusted
-        );
-        assert_eq!(
-            Amount::from_btc(5000.0).unwrap(),
-            bob.get_balances().unwrap().mine.immature
-        );
-        let _txid = alice
-            .send_to_address(
-                &bob_address,
-                Amount::from_btc(1.0).unwrap(),
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
+            .call::<Value>(
+                "createwallet",
+                &[
+                    "legacy".into(),
+                    false.into(),
+                    false.into(),
+                    "".into(),
+                    false.into(),
+                    false.into(),
+                ],
             )
             .unwrap();
-        assert!(
-            alice.get_balances().unwrap().mine.trusted < Amount::from_btc(49.0).unwrap()
-                && alice.get_balances().unwrap().mine.trusted > Amount::from_btc(48.9).unwrap()
+        let legacy = Client::new(
+            &bitcoind.rpc_url_with_wallet("legacy"),
+            Auth::CookieFile(bitcoind.params.cookie_file.clone()),
+        )
+        .unwrap();
+
+        let secp = Secp256k1::new();
+        let key = PrivateKey::new(SecretKey::from_slice(&[1; 32]).unwrap(), Network::Regtest);
+        let address = Address::p2wpkh(
+            &CompressedPublicKey::from_private_key(&secp, &key).unwrap(),
+            Network::Regtest,
         );
+        bitcoind
+            .import_private_key(&legacy, &key.to_wif(), "imported", false)
+            .unwrap();
+        assert!(legacy.get_address_info(&address).unwrap().is_mine.unwrap());
 
-        // bob wallet may not be immediately updated
-        for _ in 0..30 {
-            if bob.get_balances().unwrap().mine.untrusted_pending.to_sat() > 0 {
-                break;
-            }
-            std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(bitcoind
+            .import_private_key(&legacy, "not a wif", "imported", false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_addresses_by_label() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        assert!(bitcoind
+            .addresses_by_label(&bitcoind.client, "mining")
+            .unwrap()
+            .is_empty());
+
+        let first = bitcoind.get_receive_address().unwrap();
+        let second = bitcoind.get_receive_address().unwrap();
+        for address in [&first, &second] {
+            bitcoind
+                .set_label(&bitcoind.client, address, "mining")
+                .unwrap();
         }
+        let mut labeled = bitcoind
+            .addresses_by_label(&bitcoind.client, "mining")
+            .unwrap();
+        labeled.sort_by_key(|a| a.to_string());
+        let mut expected = vec![first, second];
+        expected.sort_by_key(|a| a.to_string());
+        assert_eq!(labeled, expected);
+    }
+
+    #[test]
+    fn test_list_transactions() {
+        use crate::TxCategory;
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        assert!(bitcoind
+            .list_transactions(&bitcoind.client, 10, 0)
+            .unwrap()
+            .is_empty());
+
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+        let history = bitcoind
+            .list_transactions(&bitcoind.client, 200, 0)
+            .unwrap();
+        assert_eq!(history.len(), 101);
+        assert_eq!(history[0].category, TxCategory::Generate);
+        assert_eq!(history[0].confirmations, 101);
+        assert!(history[1..]
+            .iter()
+            .all(|t| t.category == TxCategory::Immature));
+
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_sat(100_000))
+            .unwrap();
+        let last = bitcoind.list_transactions(&bitcoind.client, 2, 0).unwrap();
+        assert!(last.iter().all(|t| t.txid == txid && t.confirmations == 0));
+        let send = last
+            .iter()
+            .find(|t| t.category == TxCategory::Send)
+            .unwrap();
+        assert_eq!(send.amount.to_sat(), -100_000);
+        let receive = last
+            .iter()
+            .find(|t| t.category == TxCategory::Receive)
+            .unwrap();
+        assert_eq!(receive.amount.to_sat(), 100_000);
+        let before = bitcoind.list_transactions(&bitcoind.client, 1, 2).unwrap();
+        assert_eq!(before[0].category, TxCategory::Immature);
+    }
+
+    #[test]
+    fn test_get_balance_details() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(101, &address).unwrap();
+
+        let balances = bitcoind.get_balance_details().unwrap();
+        assert_eq!(balances.trusted, Amount::from_btc(50.0).unwrap());
+        assert_eq!(balances.untrusted_pending, Amount::ZERO);
+        assert_eq!(balances.immature, Amount::from_btc(5000.0).unwrap());
+        assert_eq!(balances.used, None);
+    }
+
+    #[test]
+    fn test_consolidate_utxos() {
+        let exe = init();
+        let bitcoind = BitcoinD::new(exe).unwrap();
         assert_eq!(
-            Amount::from_btc(1.0).unwrap(),
-            bob.get_balances().unwrap().mine.untrusted_pending
-        );
-        assert!(
-            bitcoind.create_wallet("bob").is_err(),
-            "wallet already exist"
+            bitcoind.consolidate_utxos(&bitcoind.client, 10).unwrap(),
+            None
         );
+
+        let address = bitcoind.get_receive_address().unwrap();
+        bitcoind.client.generate_to_address(110, &address).unwrap();
+        assert_eq!(bitcoind.list_utxos(&bitcoind.client, 1).unwrap().len(), 10);
+
+        let txid = bitcoind
+            .consolidate_utxos(&bitcoind.client, 4)
+            .unwrap()
+            .unwrap();
+        assert_eq!(bitcoind.get_tx(&txid).unwrap().input.len(), 4);
+        bitcoind.client.generate_to_address(1, &address).unwrap();
+        // 6 untouched, 1 consolidated and the new mature coinbase
+        assert_eq!(bitcoind.list_utxos(&bitcoind.client, 1).unwrap().len(), 8);
     }
 
     #[test]
@@ -799,10 +5296,20 @@ mod test {
         )
         .unwrap();
 
-        let result_values = bitcoind.params.get_cookie_values().unwrap().unwrap();
+        let result_values = bitcoind.params.get_cookie_values().unwrap();
 
         assert_eq!(user, result_values.user);
         assert_eq!(password, result_values.password);
+        match result_values.to_auth() {
+            Auth::UserPass(u, p) => assert_eq!((u.as_str(), p.as_str()), (user, password)),
+            _ => panic!("expected user and password auth"),
+        }
+
+        std::fs::write(&bitcoind.params.cookie_file, "no_separator").unwrap();
+        match bitcoind.params.get_cookie_values() {
+            Err(CookieError::MalformedContent(content)) => assert_eq!(content, "no_separator"),
+            _ => panic!("expected malformed content error"),
+        }
     }
 
     #[test]
@@ -829,6 +5336,17 @@ mod test {
         result.len()
     }
 
+    /// Wait up to 10 seconds for the node to have `expected` peers
+    fn wait_for_peers_connected(client: &Client, expected: usize) -> bool {
+        for _ in 0..100 {
+            if peers_connected(client) == expected {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        false
+    }
+
     fn init() -> String {
         let _ = env_logger::try_init();
         exe_path().unwrap()
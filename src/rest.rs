@@ -0,0 +1,99 @@
+//! Minimal client of the bitcoind REST interface, enabled with [crate::Conf::enable_rest].
+//!
+//! The REST interface doesn't require authentication and it's faster than the RPC for read-only
+//! queries, it's served on the RPC port under the `/rest` path, see [crate::BitcoinD::rest_url].
+
+use crate::bitcoincore_rpc::bitcoin::{consensus, Block, BlockHash, Transaction, Txid};
+use crate::Result;
+use anyhow::Context;
+
+/// Client of the bitcoind REST interface, data is requested in binary format and deserialized
+#[derive(Debug, Clone)]
+pub struct RestClient {
+    url: String,
+}
+
+impl RestClient {
+    /// Create a client for the given REST `url` eg. http://127.0.0.1:44842/rest
+    pub fn new<T: AsRef<str>>(url: T) -> RestClient {
+        RestClient {
+            url: url.as_ref().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Get the transaction with the given `txid`, confirmed transactions not in the mempool
+    /// require the node to run with `-txindex`
+    pub fn get_transaction(&self, txid: &Txid) -> Result<Transaction> {
+        let bytes = self.get(&format!("tx/{}.bin", txid))?;
+        Ok(consensus::deserialize(&bytes).context("invalid transaction")?)
+    }
+
+    /// Get the block with the given `hash`
+    pub fn get_block(&self, hash: &BlockHash) -> Result<Block> {
+        let bytes = self.get(&format!("block/{}.bin", hash))?;
+        Ok(consensus::deserialize(&bytes).context("invalid block")?)
+    }
+
+    fn get(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        let url = format!("{}/{}", self.url, path);
+        let resp = minreq::get(&url)
+            .send()
+            .with_context(|| format!("cannot reach url {}", url))?;
+        if resp.status_code != 200 {
+            return Err(anyhow::anyhow!(
+                "url {} returned {} {}",
+                url,
+                resp.status_code,
+                resp.as_str().unwrap_or_default()
+            ));
+        }
+        Ok(resp.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitcoincore_rpc::bitcoin::{Amount, Txid};
+    use crate::bitcoincore_rpc::RpcApi;
+    use crate::rest::RestClient;
+    use crate::{exe_path, BitcoinD, Conf};
+
+    #[test]
+    fn test_rest() {
+        let _ = env_logger::try_init();
+        let conf = Conf {
+            enable_rest: true,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        let rest = RestClient::new(bitcoind.rest_url());
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let hashes = bitcoind.client.generate_to_address(101, &address).unwrap();
+        let block = rest.get_block(&hashes[0]).unwrap();
+        assert_eq!(block.block_hash(), hashes[0]);
+
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_btc(1.0).unwrap())
+            .unwrap();
+        let tx = rest.get_transaction(&txid).unwrap();
+        assert_eq!(tx.compute_txid(), txid);
+
+        let unknown: Txid = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        assert!(rest.get_transaction(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_rest_disabled() {
+        let _ = env_logger::try_init();
+        let bitcoind = BitcoinD::new(exe_path().unwrap()).unwrap();
+        let rest = RestClient::new(bitcoind.rest_url());
+        let hash = bitcoind.client.get_best_block_hash().unwrap();
+        assert!(rest.get_block(&hash).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Extraction of the bitcoind executable from a bitcoin core release tarball, verified against the
+//! bundled `sha256` sums.
+
+use crate::bitcoincore_rpc::bitcoin::hashes::{sha256, Hash};
+use crate::Result;
+use anyhow::Context;
+use flate2::read::GzDecoder;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// The sums of the release files of the supported versions
+const SHA256SUMS: [&str; 11] = [
+    include_str!("../sha256/bitcoin-core-0.17.1-SHA256SUMS.asc"),
+    include_str!("../sha256/bitcoin-core-0.18.1-SHA256SUMS.asc"),
+    include_str!("../sha256/bitcoin-core-0.19.1-SHA256SUMS.asc"),
+    include_str!("../sha256/bitcoin-core-0.20.2-SHA256SUMS.asc"),
+    include_str!("../sha256/bitcoin-core-0.21.2-SHA256SUMS.asc"),
+    include_str!("../sha256/bitcoin-core-22.1-SHA256SUMS"),
+    include_str!("../sha256/bitcoin-core-23.1-SHA256SUMS"),
+    include_str!("../sha256/bitcoin-core-24.0.1-SHA256SUMS"),
+    include_str!("../sha256/bitcoin-core-25.0-SHA256SUMS"),
+    include_str!("../sha256/bitcoin-core-25.1-SHA256SUMS"),
+    include_str!("../sha256/bitcoin-core-26.0-SHA256SUMS"),
+];
+
+/// Returns the path of the bitcoind executable contained in the `.tar.gz` release at
+/// `tarball_path`, extracting it if needed.
+///
+/// The tarball must keep the release filename, eg. `bitcoin-25.1-x86_64-linux-gnu.tar.gz`, to
+/// find its expected hash. The executable is extracted in a directory of the OS temporary dir
+/// named after the tarball hash, so that following calls with the same tarball reuse it
+pub(crate) fn extract_bitcoind(tarball_path: &Path) -> Result<PathBuf> {
+    let filename = tarball_path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .with_context(|| format!("invalid tarball path {:?}", tarball_path))?;
+    if !filename.ends_with(".tar.gz") {
+        return Err(
+            anyhow::anyhow!("only .tar.gz tarballs are supported, got {}", filename).into(),
+        );
+    }
+    let expected_hash = expected_sha256(filename)
+        .with_context(|| format!("{} is not a known release file", filename))?;
+
+    let tarball_bytes =
+        fs::read(tarball_path).with_context(|| format!("cannot read {:?}", tarball_path))?;
+    let tarball_hash = sha256::Hash::hash(&tarball_bytes);
+    if tarball_hash != expected_hash {
+        return Err(anyhow::anyhow!(
+            "hash of {:?} is {} while {} is expected",
+            tarball_path,
+            tarball_hash,
+            expected_hash
+        )
+        .into());
+    }
+
+    let cache_dir = std::env::temp_dir()
+        .join("bitcoind-tarball")
+        .join(tarball_hash.to_string());
+    if let Some(exe) = find_bitcoind(&cache_dir) {
+        return Ok(exe);
+    }
+
+    // extract in a sibling dir renamed at the end, so that concurrent calls never see a
+    // partially written executable
+    let partial_dir = cache_dir.with_extension(format!("partial-{}", std::process::id()));
+    fs::create_dir_all(&partial_dir)?;
+    let mut archive = Archive::new(GzDecoder::new(&tarball_bytes[..]));
+    for mut entry in archive.entries()?.flatten() {
+        if entry.path().map_or(false, |p| p.ends_with("bitcoind")) {
+            entry.unpack_in(&partial_dir)?;
+        }
+    }
+    if fs::rename(&partial_dir, &cache_dir).is_err() {
+        // another process extracted it in the meantime
+        let _ = fs::remove_dir_all(&partial_dir);
+    }
+    find_bitcoind(&cache_dir)
+        .with_context(|| format!("bitcoind not found in {:?}", tarball_path))
+        .map_err(Into::into)
+}
+
+/// The expected hash of the release file `filename`, if known
+fn expected_sha256(filename: &str) -> Option<sha256::Hash> {
+    SHA256SUMS
+        .iter()
+        .flat_map(|sums| sums.lines())
+        .filter_map(|line| line.split_once("  "))
+        .find(|(_, name)| *name == filename)
+        .and_then(|(hash, _)| hash.parse().ok())
+}
+
+/// Find the `bin/bitcoind` executable extracted in `dir`, which is inside the `bitcoin-<version>`
+/// directory
+fn find_bitcoind(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path().join("bin").join("bitcoind"))
+        .find(|exe| exe.exists())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tarball::{expected_sha256, extract_bitcoind};
+    use std::path::Path;
+
+    #[test]
+    fn test_expected_sha256() {
+        assert_eq!(
+            expected_sha256("bitcoin-0.17.1-x86_64-linux-gnu.tar.gz")
+                .unwrap()
+                .to_string(),
+            "53ffca45809127c9ba33ce0080558634101ec49de5224b2998c489b6d0fc2b17"
+        );
+        assert!(expected_sha256("bitcoin-0.16.0-x86_64-linux-gnu.tar.gz").is_none());
+    }
+
+    #[test]
+    fn test_extract_bitcoind_wrong_hash() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("bitcoin-26.0-x86_64-linux-gnu.tar.gz");
+        std::fs::write(&path, b"not a tarball").unwrap();
+        assert!(extract_bitcoind(&path).is_err());
+        assert!(extract_bitcoind(Path::new("/tmp/bitcoind.zip")).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Setup patterns shared by the test suites of crates depending on this one, enabled with the
+//! `test_utils` feature.
+//!
+//! Intended for test use only: errors aren't recovered and the amounts are arbitrary, enable the
+//! feature in `[dev-dependencies]` only.
+
+use crate::bitcoincore_rpc::RpcApi;
+#[cfg(any(feature = "0_19_1", not(feature = "download")))]
+use crate::bitcoincore_rpc::{bitcoin::Amount, Client};
+use crate::{exe_path, BitcoinD, Conf, Result};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Launch a node, found with [exe_path], whose default wallet has a spendable coinbase of 50
+/// BTC, 101 blocks are mined to it
+pub fn create_funded_node() -> Result<BitcoinD> {
+    let conf = Conf {
+        initial_blocks: 101,
+        ..Conf::default()
+    };
+    BitcoinD::with_conf(exe_path()?, &conf)
+}
+
+#[cfg(any(feature = "0_19_1", not(feature = "download")))]
+/// Create the `alice` and `bob` wallets in `node`, funded with 1 BTC each from the default wallet
+/// and confirmed, so `node` must have spendable funds like the one of [create_funded_node]
+pub fn two_funded_wallets(node: &BitcoinD) -> Result<(Client, Client)> {
+    let alice = node.create_wallet("alice")?;
+    let bob = node.create_wallet("bob")?;
+    for wallet in [&alice, &bob] {
+        let address = wallet.get_new_address(None, None)?.assume_checked();
+        node.send_to_address(&address, Amount::ONE_BTC)?;
+    }
+    let address = node.get_receive_address()?;
+    node.client.generate_to_address(1, &address)?;
+    Ok((alice, bob))
+}
+
+/// Mine `blocks` blocks on the first of `nodes` and wait, up to 30 seconds, for all the others
+/// to have the same tip, so they must be connected
+pub fn mine_and_sync(nodes: &[&BitcoinD], blocks: u64) -> Result<()> {
+    let miner = match nodes.first() {
+        Some(miner) => miner,
+        None => return Ok(()),
+    };
+    let address = miner.get_receive_address()?;
+    miner.client.generate_to_address(blocks, &address)?;
+    let tip = miner.client.get_best_block_hash()?;
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    for node in &nodes[1..] {
+        while node.client.get_best_block_hash()? != tip {
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!("nodes not synced to {} in 30 seconds", tip).into());
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitcoincore_rpc::RpcApi;
+    use crate::test_utils::{create_funded_node, mine_and_sync};
+    use crate::{exe_path, BitcoinD, Conf, P2P};
+
+    #[test]
+    fn test_test_utils() {
+        let node = create_funded_node().unwrap();
+        assert_eq!(101, node.client.get_block_count().unwrap());
+
+        #[cfg(any(feature = "0_19_1", not(feature = "download")))]
+        {
+            let (alice, bob) = crate::test_utils::two_funded_wallets(&node).unwrap();
+            for wallet in [alice, bob] {
+                let balance = wallet.get_balance(None, None).unwrap();
+                assert_eq!(balance.to_btc(), 1.0);
+            }
+        }
+
+        let conf = Conf {
+            p2p: P2P::Yes,
+            ..Conf::default()
+        };
+        let first = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        let conf = Conf {
+            p2p: first.p2p_connect(false).unwrap(),
+            ..Conf::default()
+        };
+        let second = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        mine_and_sync(&[&first, &second], 3).unwrap();
+        assert_eq!(3, second.client.get_block_count().unwrap());
+    }
+}
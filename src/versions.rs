@@ -1,3 +1,6 @@
+// Every version feature enables the previous ones, the cfg chain selects the newest enabled version.
+// Multiple version features are therefore always enabled and mustn't be treated as an error.
+
 #[cfg(feature = "26_0")]
 pub const VERSION: &str = "26.0";
 
@@ -30,3 +33,11 @@ pub const VERSION: &str = "0.18.1";
 
 #[cfg(all(feature = "0_17_1", not(feature = "0_18_1")))]
 pub const VERSION: &str = "0.17.1";
+
+/// All the versions which can be downloaded with the corresponding feature, eg. `"0.21.2"` with
+/// feature `0_21_2`, sorted from oldest to newest
+#[allow(dead_code)] // unused in build.rs which includes this file
+pub const SUPPORTED_VERSIONS: &[&str] = &[
+    "0.17.1", "0.18.1", "0.19.1", "0.20.2", "0.21.2", "22.1", "23.1", "24.0.1", "25.0", "25.1",
+    "26.0",
+];
@@ -0,0 +1,115 @@
+//! Subscribers of the bitcoind ZMQ notifications, enabled with [crate::Conf::enable_zmq].
+//!
+//! Requires the `zmq` feature, sockets are found in [crate::ConnectParams].
+
+use crate::bitcoincore_rpc::bitcoin::{consensus, Block, Transaction};
+use crate::Result;
+use anyhow::Context;
+use std::net::SocketAddrV4;
+
+/// Subscriber of the blocks published on [crate::ConnectParams::zmq_pub_raw_block_socket]
+pub struct BlockSubscriber(Subscriber);
+
+/// Subscriber of the transactions published on [crate::ConnectParams::zmq_pub_raw_tx_socket]
+pub struct TxSubscriber(Subscriber);
+
+impl BlockSubscriber {
+    /// Subscribe to the `rawblock` topic published on `socket`
+    pub fn new(socket: &SocketAddrV4) -> Result<BlockSubscriber> {
+        Ok(BlockSubscriber(Subscriber::new(socket, "rawblock")?))
+    }
+
+    /// Wait for the next block connected by the node
+    pub fn next_block(&self) -> Result<Block> {
+        Ok(consensus::deserialize(&self.0.next_message()?).context("invalid block")?)
+    }
+}
+
+impl TxSubscriber {
+    /// Subscribe to the `rawtx` topic published on `socket`
+    pub fn new(socket: &SocketAddrV4) -> Result<TxSubscriber> {
+        Ok(TxSubscriber(Subscriber::new(socket, "rawtx")?))
+    }
+
+    /// Wait for the next transaction entering the mempool or confirmed in a block
+    pub fn next_tx(&self) -> Result<Transaction> {
+        Ok(consensus::deserialize(&self.0.next_message()?).context("invalid transaction")?)
+    }
+}
+
+struct Subscriber {
+    // the socket must not outlive its context
+    socket: ::zmq::Socket,
+    _context: ::zmq::Context,
+    topic: &'static str,
+}
+
+impl Subscriber {
+    fn new(socket: &SocketAddrV4, topic: &'static str) -> anyhow::Result<Subscriber> {
+        let context = ::zmq::Context::new();
+        let sub = context.socket(::zmq::SUB)?;
+        let url = format!("tcp://{}", socket);
+        sub.connect(&url)
+            .with_context(|| format!("cannot connect to {}", url))?;
+        sub.set_subscribe(topic.as_bytes())?;
+        Ok(Subscriber {
+            socket: sub,
+            _context: context,
+            topic,
+        })
+    }
+
+    /// Returns the body of the next message, bitcoind messages are made of 3 parts: topic, body
+    /// and sequence number
+    fn next_message(&self) -> anyhow::Result<Vec<u8>> {
+        let mut parts = self.socket.recv_multipart(0)?;
+        if parts.len() != 3 || parts[0] != self.topic.as_bytes() {
+            return Err(anyhow::anyhow!(
+                "unexpected zmq message with {} parts on topic {}",
+                parts.len(),
+                self.topic
+            ));
+        }
+        Ok(parts.swap_remove(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitcoincore_rpc::bitcoin::Amount;
+    use crate::bitcoincore_rpc::RpcApi;
+    use crate::zmq::{BlockSubscriber, TxSubscriber};
+    use crate::{exe_path, BitcoinD, Conf};
+
+    #[test]
+    fn test_zmq_subscribers() {
+        let _ = env_logger::try_init();
+        let conf = Conf {
+            enable_zmq: true,
+            ..Conf::default()
+        };
+        let bitcoind = BitcoinD::with_conf(exe_path().unwrap(), &conf).unwrap();
+        let blocks =
+            BlockSubscriber::new(&bitcoind.params.zmq_pub_raw_block_socket.unwrap()).unwrap();
+        let txs = TxSubscriber::new(&bitcoind.params.zmq_pub_raw_tx_socket.unwrap()).unwrap();
+        // subscriptions take a while to be propagated to the publisher
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let address = bitcoind
+            .client
+            .get_new_address(None, None)
+            .unwrap()
+            .assume_checked();
+        let hashes = bitcoind.client.generate_to_address(101, &address).unwrap();
+        assert_eq!(blocks.next_block().unwrap().block_hash(), hashes[0]);
+
+        // skip the coinbases of the generated blocks
+        for _ in 0..101 {
+            assert!(txs.next_tx().unwrap().is_coinbase());
+        }
+        let txid = bitcoind
+            .send_to_address(&address, Amount::from_btc(1.0).unwrap())
+            .unwrap();
+        assert_eq!(txs.next_tx().unwrap().compute_txid(), txid);
+    }
+}